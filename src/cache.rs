@@ -0,0 +1,163 @@
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::hashing::{is_sha256, sha256_bytes};
+use std::collections::HashSet;
+use crate::history::current_revision_hashes;
+use crate::storage::data_subdir;
+
+const CACHE_FOLDER:&str = "cache";
+pub(crate) const DEFAULT_CACHE_LIMIT_MB:u64 = 2048;
+
+/// Downloaded pack files are kept in the data dir by their sha256, so files shared
+/// between packs and revisions are only fetched once. The hash comes from the pack host,
+/// anything but a plain sha256 is refused so it can not point outside the cache.
+fn cache_path(sha256: &str) -> Result<PathBuf, String>{
+    if !is_sha256(sha256) {
+        return Err(format!("'{sha256}' is not a sha256 hash"));
+    }
+    Ok(data_subdir(CACHE_FOLDER)?.join(sha256))
+}
+
+pub fn is_cached(sha256: &str) -> bool{
+    cache_path(sha256).map(|path| path.exists()).unwrap_or(false)
+}
+
+/// Returns the cached bytes for a hash, or `None` if the file is missing or was modified.
+pub fn read_cached(sha256: &str) -> Option<Vec<u8>>{
+    let path = cache_path(sha256).ok()?;
+    let bytes = fs::read(&path).ok()?;
+    if sha256_bytes(&bytes) != sha256 {
+        log::warn!("dropping corrupted cache entry {sha256}");
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+    // the modification time doubles as the last use for trimming
+    let _ = File::options().write(true).open(&path).and_then(|file| file.set_modified(SystemTime::now()));
+    Some(bytes)
+}
+
+pub fn store_cached(sha256: &str, bytes: &[u8]) -> Result<(), String>{
+    let path = cache_path(sha256)?;
+    if path.exists() {
+        return Ok(());
+    }
+    match fs::write(&path, bytes) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{0}: {err}", path.display())),
+    }
+}
+
+/// Places a cached file at `dest`, as a hard link when the filesystem allows it.
+pub fn link_cached(sha256: &str, dest: &Path) -> Result<(), String>{
+    let path = cache_path(sha256)?;
+    let _ = fs::remove_file(dest);
+    if fs::hard_link(&path, dest).is_ok() {
+        return Ok(());
+    }
+    match fs::copy(&path, dest) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{0}: {err}", dest.display())),
+    }
+}
+
+fn cache_entries(dir: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>, String>{
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => return Err(err.to_string()),
+    };
+    Ok(entries.flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.len(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect())
+}
+
+/// Total size of the cache in bytes.
+pub fn cache_size() -> Result<u64, String>{
+    Ok(cache_entries(&data_subdir(CACHE_FOLDER)?)?.iter().map(|(_, size, _)| size).sum())
+}
+
+/// Removes the least recently used files until the cache fits in `limit_mb`. Files of the
+/// current revision of a pack are kept. Returns the number of bytes freed.
+pub fn trim_cache(limit_mb: u64) -> Result<u64, String>{
+    trim_dir(&data_subdir(CACHE_FOLDER)?, limit_mb * 1024 * 1024, &current_revision_hashes())
+}
+
+/// Empties the cache and returns the number of bytes freed.
+pub fn clear_cache() -> Result<u64, String>{
+    trim_dir(&data_subdir(CACHE_FOLDER)?, 0, &HashSet::new())
+}
+
+fn trim_dir(dir: &Path, limit: u64, keep: &HashSet<String>) -> Result<u64, String>{
+    let mut entries = cache_entries(dir)?;
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    let mut total = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+    let mut freed = 0;
+    for (path, size, _) in entries {
+        if total <= limit {
+            break;
+        }
+        let hash = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if keep.contains(&hash) {
+            continue;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+            freed += size;
+        }
+    }
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+    use super::trim_dir;
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("qmodmanager-cache-{name}-{0}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes `count` cache entries of 1 KiB, `entry0` being the least recently used.
+    fn fill(dir: &Path, count: u64){
+        let now = SystemTime::now();
+        for i in 0..count {
+            let path = dir.join(format!("entry{i}"));
+            fs::write(&path, vec![0u8; 1024]).unwrap();
+            let used = now - Duration::from_secs(1000 - i);
+            File::options().write(true).open(&path).unwrap().set_modified(used).unwrap();
+        }
+    }
+
+    #[test]
+    fn trimming_drops_the_oldest_files_until_the_limit_fits() {
+        let dir = temp_dir("limit");
+        fill(&dir, 5);
+        assert_eq!(trim_dir(&dir, 3 * 1024, &HashSet::new()).unwrap(), 2 * 1024);
+        assert!(!dir.join("entry0").exists() && !dir.join("entry1").exists());
+        assert!(dir.join("entry2").exists() && dir.join("entry4").exists());
+        assert_eq!(trim_dir(&dir, 3 * 1024, &HashSet::new()).unwrap(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn kept_files_are_skipped_and_zero_empties_the_rest() {
+        let dir = temp_dir("keep");
+        fill(&dir, 4);
+        let keep = HashSet::from(["entry0".to_string()]);
+        assert_eq!(trim_dir(&dir, 2 * 1024, &keep).unwrap(), 2 * 1024);
+        assert!(dir.join("entry0").exists() && !dir.join("entry1").exists() && !dir.join("entry2").exists());
+        assert_eq!(trim_dir(&dir, 0, &HashSet::new()).unwrap(), 2 * 1024);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
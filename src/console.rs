@@ -0,0 +1,104 @@
+use eframe::egui::{Align, Layout, RichText, ScrollArea, TextEdit, TextStyle, Ui};
+use crate::App;
+use crate::log::{LogEntry, LogLevel};
+
+pub struct ConsoleFilter{
+    pub show_error:bool,
+    pub show_warn:bool,
+    pub show_info:bool,
+    pub show_debug:bool,
+    pub search:String,
+}
+
+impl Default for ConsoleFilter {
+    fn default() -> Self {
+        Self {
+            show_error: true,
+            show_warn: true,
+            show_info: true,
+            show_debug: false,
+            search: "".to_string(),
+        }
+    }
+}
+
+impl ConsoleFilter{
+    fn level_toggle(&mut self, level: LogLevel) -> &mut bool{
+        match level {
+            LogLevel::Error => &mut self.show_error,
+            LogLevel::Warn => &mut self.show_warn,
+            LogLevel::Info => &mut self.show_info,
+            LogLevel::Debug => &mut self.show_debug,
+        }
+    }
+    fn shows_level(&self, level: LogLevel) -> bool{
+        match level {
+            LogLevel::Error => self.show_error,
+            LogLevel::Warn => self.show_warn,
+            LogLevel::Info => self.show_info,
+            LogLevel::Debug => self.show_debug,
+        }
+    }
+    fn matches(&self, entry: &LogEntry) -> bool{
+        if !self.shows_level(entry.level) {
+            return false;
+        }
+        if self.search.is_empty() {
+            return true;
+        }
+        let search = self.search.to_lowercase();
+        entry.message.to_lowercase().contains(&search) || entry.source.to_lowercase().contains(&search)
+    }
+}
+
+/// Debug console with level filters and search. Only the rows in view are laid out, so the
+/// log can grow without slowing down every frame.
+pub fn console_panel(ui: &mut Ui, app: &mut App){
+    ui.horizontal(|ui| {
+        for level in LogLevel::vec_all() {
+            let label = RichText::new(level.as_str()).color(level.color());
+            ui.checkbox(app.console_filter.level_toggle(level), label);
+        }
+        ui.separator();
+        ui.add(TextEdit::singleline(&mut app.console_filter.search).hint_text("Search").desired_width(150.0));
+    });
+
+    let visible = app.log_entries.iter()
+        .filter(|entry| app.console_filter.matches(entry))
+        .collect::<Vec<&LogEntry>>();
+
+    ui.horizontal(|ui| {
+        if ui.button("Copy").clicked() {
+            let text = visible.iter().map(|entry| entry.format_line()).collect::<String>();
+            ui.ctx().copy_text(text);
+        }
+        ui.label(format!("{0} of {1} entries", visible.len(), app.log_entries.len()));
+    });
+    ui.separator();
+
+    // show_rows needs rows of one height, so multi line messages (error chains) get a row per line
+    let rows = visible.iter()
+        .flat_map(|entry| entry.message.split('\n').enumerate().map(move |(index, line)| (*entry, index, line.trim_end_matches('\r'))))
+        .collect::<Vec<(&LogEntry, usize, &str)>>();
+
+    let row_height = ui.text_style_height(&TextStyle::Monospace);
+    ScrollArea::both()
+        .auto_shrink(false)
+        .stick_to_bottom(true)
+        .show_rows(ui, row_height, rows.len(), |ui, row_range| {
+            ui.with_layout(Layout::top_down(Align::LEFT), |ui| {
+                for (entry, index, line) in &rows[row_range] {
+                    ui.horizontal(|ui| {
+                        if *index == 0 {
+                            ui.label(RichText::new(entry.format_time()).monospace().weak());
+                            ui.label(RichText::new(format!("[{0}]", entry.level.as_str())).monospace().color(entry.level.color()));
+                            ui.label(RichText::new(&entry.source).monospace().weak());
+                            ui.label(RichText::new(*line).monospace());
+                        } else {
+                            ui.label(RichText::new(format!("    {line}")).monospace());
+                        }
+                    });
+                }
+            });
+        });
+}
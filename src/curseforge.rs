@@ -0,0 +1,409 @@
+use std::fs::File;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use serde::Deserialize;
+use zip::ZipArchive;
+use crate::hashing::sha1_bytes;
+use crate::local_pack::{local_pack_id, write_local_pack, ImportReport};
+use crate::manifest::{PackFile, PackManifest};
+use crate::http::{is_web_url, HttpSettings};
+use crate::pack::fetch_bytes;
+
+const MANIFEST_FILE:&str = "manifest.json";
+const API_URL:&str = "https://api.curseforge.com/v1";
+/// `algo` value of sha1 hashes in the CurseForge api
+const SHA1_ALGO:u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeManifest{
+    minecraft:CurseForgeMinecraft,
+    name:String,
+    #[serde(default)]
+    files:Vec<CurseForgeFile>,
+    #[serde(default = "default_overrides")]
+    overrides:String,
+}
+
+fn default_overrides() -> String{
+    "overrides".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeMinecraft{
+    version:String,
+    #[serde(default)]
+    mod_loaders:Vec<CurseForgeModLoader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurseForgeModLoader{
+    /// e.g. `forge-47.3.10`
+    id:String,
+    #[serde(default)]
+    primary:bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseForgeFile{
+    #[serde(rename = "projectID")]
+    project_id:u64,
+    #[serde(rename = "fileID")]
+    file_id:u64,
+    #[serde(default = "default_required")]
+    required:bool,
+}
+
+fn default_required() -> bool{
+    true
+}
+
+/// Where a CurseForge project file can be downloaded from.
+#[derive(Debug, Clone)]
+pub struct ResolvedFile{
+    pub file_name:String,
+    pub download_url:String,
+    pub sha1:Option<String>,
+}
+
+pub type ResolveFuture<'a> = Pin<Box<dyn Future<Output = Result<ResolvedFile, String>> + Send + 'a>>;
+
+/// Turns the project and file ids of a CurseForge manifest into downloadable files. The
+/// importer only talks to the resolver, so a local stub can stand in for the live api.
+pub trait FileResolver: Send + Sync {
+    fn resolve(&self, project_id: u64, file_id: u64) -> ResolveFuture<'_>;
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse{
+    data:ApiFile,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiFile{
+    file_name:String,
+    download_url:Option<String>,
+    #[serde(default)]
+    hashes:Vec<ApiHash>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiHash{
+    value:String,
+    algo:u32,
+}
+
+/// Resolves files through the CurseForge api, which needs an api key.
+pub struct CurseForgeApiResolver{
+    pub api_key:String,
+}
+
+impl FileResolver for CurseForgeApiResolver {
+    fn resolve(&self, project_id: u64, file_id: u64) -> ResolveFuture<'_> {
+        Box::pin(async move {
+            let url = format!("{API_URL}/mods/{project_id}/files/{file_id}");
+            let client = HttpSettings::default().client()?;
+            let response = client
+                .get(&url)
+                .header("x-api-key", &self.api_key)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => return Err(format!("{url}: {err}")),
+            };
+            let file = match response.json::<ApiResponse>().await {
+                Ok(response) => response.data,
+                Err(err) => return Err(format!("{url}: {err}")),
+            };
+            let download_url = match file.download_url {
+                // fetch_bytes would read file:// urls from disk
+                Some(download_url) if !is_web_url(&download_url) => return Err(format!("{0} (project {project_id}) has the non-http download url {download_url}", file.file_name)),
+                Some(download_url) => download_url,
+                None => return Err(format!("{0} (project {project_id}) does not allow third party downloads, download it manually", file.file_name)),
+            };
+            Ok(ResolvedFile{
+                sha1: file.hashes.into_iter().find(|hash| hash.algo == SHA1_ALGO).map(|hash| hash.value),
+                file_name: file.file_name,
+                download_url,
+            })
+        })
+    }
+}
+
+/// The manifest and override files, read up front so no zip handle is held across the downloads.
+struct CurseForgeContent{
+    manifest:CurseForgeManifest,
+    overrides:Vec<(String, Vec<u8>)>,
+}
+
+fn read_curseforge_zip(archive_path: &Path) -> Result<CurseForgeContent, String>{
+    let file = match File::open(archive_path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("{0}: {err}", archive_path.display())),
+    };
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let manifest: CurseForgeManifest = {
+        let mut entry = match archive.by_name(MANIFEST_FILE) {
+            Ok(entry) => entry,
+            Err(_) => return Err(format!("{MANIFEST_FILE} is missing, this is not a CurseForge pack")),
+        };
+        let mut text = String::new();
+        if let Err(err) = entry.read_to_string(&mut text) {
+            return Err(err.to_string());
+        }
+        match serde_json::from_str(&text) {
+            Ok(manifest) => manifest,
+            Err(err) => return Err(format!("malformed {MANIFEST_FILE}: {err}")),
+        }
+    };
+
+    let prefix = format!("{0}/", manifest.overrides.trim_end_matches('/'));
+    let mut overrides = vec![];
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(err) => return Err(err.to_string()),
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        let path = match entry.name().strip_prefix(&prefix) {
+            Some(path) => path.to_string(),
+            None => continue,
+        };
+        let mut bytes = vec![];
+        if let Err(err) = entry.read_to_end(&mut bytes) {
+            return Err(err.to_string());
+        }
+        overrides.push((path, bytes));
+    }
+    Ok(CurseForgeContent{ manifest, overrides })
+}
+
+/// A pack read from a CurseForge export, before it is written to disk.
+struct CurseForgePack{
+    manifest:PackManifest,
+    files:Vec<(PackFile, Vec<u8>)>,
+    notes:Vec<String>,
+}
+
+/// Imports a CurseForge modpack export into a local pack. The minecraft and forge versions
+/// from the manifest are stored with the pack, mods are resolved through `resolver`.
+pub async fn import_curseforge(archive_path: PathBuf, resolver: Box<dyn FileResolver>) -> Result<ImportReport, String>{
+    let content = read_curseforge_zip(&archive_path)?;
+    let pack = build_pack(content, resolver.as_ref()).await?;
+    let pack_id = local_pack_id(&pack.manifest.name);
+    write_local_pack(&pack_id, &pack.manifest, &pack.files)?;
+    Ok(ImportReport{
+        pack_id,
+        name: pack.manifest.name,
+        file_count: pack.files.len(),
+        notes: pack.notes,
+    })
+}
+
+async fn build_pack(content: CurseForgeContent, resolver: &dyn FileResolver) -> Result<CurseForgePack, String>{
+    let client = HttpSettings::default().client()?;
+    let cf_manifest = content.manifest;
+    let mut notes = vec![];
+
+    let loader = cf_manifest.minecraft.mod_loaders.iter()
+        .find(|loader| loader.primary)
+        .or(cf_manifest.minecraft.mod_loaders.first());
+    let forge_version = match loader.map(|loader| loader.id.split_once('-')) {
+        Some(Some(("forge", version))) => Some(version.to_string()),
+        Some(Some((other, _))) => {
+            notes.push(format!("this pack uses {other}, only forge is supported"));
+            None
+        }
+        _ => None,
+    };
+
+    let mut files: Vec<(PackFile, Vec<u8>)> = vec![];
+    for entry in &cf_manifest.files {
+        let resolved = resolver.resolve(entry.project_id, entry.file_id).await?;
+        let bytes = fetch_bytes(&client, &resolved.download_url).await?;
+        if let Some(sha1) = &resolved.sha1 {
+            if !sha1.eq_ignore_ascii_case(&sha1_bytes(&bytes)) {
+                return Err(format!("{0}: sha1 does not match", resolved.file_name));
+            }
+        }
+        let mut file = PackFile::new(&resolved.file_name);
+        file.install_path()?;
+        // files marked as not required are shipped disabled
+        if !entry.required {
+            file.optional = true;
+            file.enabled_by_default = false;
+        }
+        files.push((file, bytes));
+    }
+
+    for (path, bytes) in content.overrides {
+        let file = match PackFile::from_install_path(&path) {
+            Some(file) => file,
+            None => {
+                notes.push(format!("skipped override {path}, packs can not install files there"));
+                continue;
+            }
+        };
+        files.retain(|(existing, _)| existing.remote_path() != file.remote_path());
+        files.push((file, bytes));
+    }
+
+    let manifest = PackManifest{
+        name: cf_manifest.name,
+        minecraft_version: Some(cf_manifest.minecraft.version.clone()),
+        forge_version,
+        files: files.iter().map(|(file, _)| file.clone()).collect(),
+        ..PackManifest::default()
+    };
+    Ok(CurseForgePack{ manifest, files, notes })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+    use crate::hashing::sha1_bytes;
+    use super::{build_pack, read_curseforge_zip, CurseForgeContent, FileResolver, ResolveFuture, ResolvedFile};
+
+    /// Serves files from a local folder in place of the CurseForge api.
+    struct StubResolver{
+        dir:PathBuf,
+        files:HashMap<u64, (String, Option<String>)>,
+    }
+
+    impl FileResolver for StubResolver {
+        fn resolve(&self, project_id: u64, file_id: u64) -> ResolveFuture<'_> {
+            Box::pin(async move {
+                match self.files.get(&file_id) {
+                    Some((file_name, sha1)) => Ok(ResolvedFile{
+                        file_name: file_name.clone(),
+                        download_url: format!("file://{0}", self.dir.join(file_name).display()),
+                        sha1: sha1.clone(),
+                    }),
+                    None => Err(format!("project {project_id} has no file {file_id}")),
+                }
+            })
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("qmodmanager-curseforge-{name}-{0}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a CurseForge export with `manifest` and the given override files and reads it back.
+    fn content(dir: &Path, manifest: &str, overrides: &[(&str, &[u8])]) -> CurseForgeContent{
+        let path = dir.join("pack.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("manifest.json", SimpleFileOptions::default()).unwrap();
+        zip.write_all(manifest.as_bytes()).unwrap();
+        for (name, bytes) in overrides {
+            zip.start_file(format!("overrides/{name}"), SimpleFileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+        read_curseforge_zip(&path).unwrap()
+    }
+
+    /// A resolver for `a.jar` (file 1) and `b.jar` (file 2), `b.jar` is listed with a wrong sha1
+    /// when `bad_sha1` is set.
+    fn resolver(dir: &Path, bad_sha1: bool) -> StubResolver{
+        fs::write(dir.join("a.jar"), b"a").unwrap();
+        fs::write(dir.join("b.jar"), b"b").unwrap();
+        let b_sha1 = match bad_sha1 {
+            true => sha1_bytes(b"not b"),
+            false => sha1_bytes(b"b"),
+        };
+        StubResolver{
+            dir: dir.to_path_buf(),
+            files: HashMap::from([
+                (1, ("a.jar".to_string(), Some(sha1_bytes(b"a")))),
+                (2, ("b.jar".to_string(), Some(b_sha1))),
+            ]),
+        }
+    }
+
+    /// A manifest listing `a.jar` as required and `b.jar` as not required.
+    fn manifest(loaders: &str) -> String{
+        format!(r#"{{
+            "minecraft": {{"version": "1.20.1", "modLoaders": [{loaders}]}},
+            "name": "Test Pack",
+            "files": [
+                {{"projectID": 10, "fileID": 1, "required": true}},
+                {{"projectID": 20, "fileID": 2, "required": false}}
+            ]
+        }}"#)
+    }
+
+    const FORGE_LOADERS:&str = r#"{"id": "fabric-0.15.0"}, {"id": "forge-47.3.10", "primary": true}"#;
+
+    #[tokio::test]
+    async fn versions_come_from_the_primary_loader() {
+        let dir = temp_dir("versions");
+        let pack = build_pack(content(&dir, &manifest(FORGE_LOADERS), &[]), &resolver(&dir, false)).await.unwrap();
+        assert_eq!(pack.manifest.name, "Test Pack");
+        assert_eq!(pack.manifest.minecraft_version.as_deref(), Some("1.20.1"));
+        assert_eq!(pack.manifest.forge_version.as_deref(), Some("47.3.10"));
+        assert!(pack.notes.is_empty());
+
+        let fabric = manifest(r#"{"id": "fabric-0.15.0", "primary": true}, {"id": "forge-47.3.10"}"#);
+        let pack = build_pack(content(&dir, &fabric, &[]), &resolver(&dir, false)).await.unwrap();
+        assert_eq!(pack.manifest.forge_version, None);
+        assert_eq!(pack.notes.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn mismatched_sha1_fails_the_import() {
+        let dir = temp_dir("sha1");
+        let err = build_pack(content(&dir, &manifest(FORGE_LOADERS), &[]), &resolver(&dir, true)).await.err().unwrap();
+        assert!(err.contains("b.jar: sha1 does not match"), "{err}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn optional_files_are_disabled() {
+        let dir = temp_dir("optional");
+        let pack = build_pack(content(&dir, &manifest(FORGE_LOADERS), &[]), &resolver(&dir, false)).await.unwrap();
+        let a = pack.manifest.files.iter().find(|file| file.name == "a.jar").unwrap();
+        let b = pack.manifest.files.iter().find(|file| file.name == "b.jar").unwrap();
+        assert!(!a.optional && a.enabled_by_default);
+        assert!(b.optional && !b.enabled_by_default);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn overrides_replace_resolved_files() {
+        let dir = temp_dir("overrides");
+        let overrides: &[(&str, &[u8])] = &[
+            ("mods/a.jar", b"patched a"),
+            ("config/jei.toml", b"jei"),
+            ("saves/world/level.dat", b"nope"),
+        ];
+        let pack = build_pack(content(&dir, &manifest(FORGE_LOADERS), overrides), &resolver(&dir, false)).await.unwrap();
+        let paths: Vec<String> = pack.files.iter().map(|(file, _)| file.remote_path()).collect();
+        assert_eq!(paths, vec!["b.jar", "a.jar", "config/jei.toml"]);
+        assert_eq!(pack.files[1].1, b"patched a".to_vec());
+        assert_eq!(pack.notes.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
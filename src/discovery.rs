@@ -0,0 +1,117 @@
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use serde::Deserialize;
+use crate::http::is_web_url;
+
+/// Companion servers listen for this message on the discovery port and answer with a
+/// json [`DiscoveryReply`].
+const DISCOVERY_MESSAGE:&[u8] = b"QMODMANAGER_DISCOVER";
+const DISCOVERY_PORT:u16 = 7879;
+const DEFAULT_PACK_PORT:u16 = 7878;
+const DISCOVERY_TIMEOUT:Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize)]
+struct DiscoveryReply{
+    name:String,
+    /// Full pack url, servers behind a proxy or with https send this.
+    #[serde(default)]
+    url:Option<String>,
+    /// Port of the pack server on the replying machine, used when no url is sent.
+    #[serde(default)]
+    port:Option<u16>,
+}
+
+/// A pack server that answered on the local network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredHost{
+    pub name:String,
+    pub url:String,
+}
+
+/// Broadcasts a discovery request and collects the answers until the timeout.
+pub async fn discover_hosts() -> Result<Vec<DiscoveredHost>, String>{
+    match tokio::task::spawn_blocking(discover_hosts_blocking).await {
+        Ok(result) => result,
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn discover_hosts_blocking() -> Result<Vec<DiscoveredHost>, String>{
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) {
+        Ok(socket) => socket,
+        Err(err) => return Err(err.to_string()),
+    };
+    let setup = socket.set_broadcast(true)
+        .and_then(|_| socket.set_read_timeout(Some(Duration::from_millis(200))))
+        .and_then(|_| socket.send_to(DISCOVERY_MESSAGE, (Ipv4Addr::BROADCAST, DISCOVERY_PORT)));
+    if let Err(err) = setup {
+        return Err(format!("could not send the discovery broadcast: {err}"));
+    }
+
+    let mut hosts: Vec<DiscoveredHost> = vec![];
+    let mut buffer = [0u8; 2048];
+    let started = Instant::now();
+    while started.elapsed() < DISCOVERY_TIMEOUT {
+        let (length, sender) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            // e.g. WSAECONNRESET on windows, which would fail again right away
+            Err(err) => {
+                log::debug!("stopped listening for discovery replies: {err}");
+                break;
+            }
+        };
+        let host = match parse_reply(&buffer[..length], sender) {
+            Some(host) => host,
+            None => {
+                log::debug!("ignoring malformed discovery reply from {sender}");
+                continue;
+            }
+        };
+        if !hosts.iter().any(|known| known.url == host.url) {
+            hosts.push(host);
+        }
+    }
+    Ok(hosts)
+}
+
+fn parse_reply(bytes: &[u8], sender: SocketAddr) -> Option<DiscoveredHost>{
+    let reply: DiscoveryReply = serde_json::from_slice(bytes).ok()?;
+    let url = match reply.url {
+        Some(url) if is_web_url(&url) => url.trim_end_matches('/').to_string(),
+        Some(url) => {
+            log::debug!("ignoring discovery reply from {sender} with the non-http url {url}");
+            return None;
+        }
+        None => format!("http://{0}:{1}", sender.ip(), reply.port.unwrap_or(DEFAULT_PACK_PORT)),
+    };
+    Some(DiscoveredHost{ name: reply.name, url })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use super::parse_reply;
+
+    fn sender() -> SocketAddr{
+        "192.168.1.20:50000".parse().unwrap()
+    }
+
+    #[test]
+    fn replies_without_url_use_the_sender() {
+        let host = parse_reply(br#"{"name": "Box", "port": 9000}"#, sender()).unwrap();
+        assert_eq!(host.url, "http://192.168.1.20:9000");
+        let host = parse_reply(br#"{"name": "Box"}"#, sender()).unwrap();
+        assert_eq!(host.url, "http://192.168.1.20:7878");
+    }
+
+    #[test]
+    fn only_web_urls_are_accepted() {
+        let host = parse_reply(br#"{"name": "Box", "url": "https://packs.lan/"}"#, sender()).unwrap();
+        assert_eq!(host.url, "https://packs.lan");
+        assert!(parse_reply(br#"{"name": "Box", "url": "file:///C:/Users"}"#, sender()).is_none());
+        assert!(parse_reply(br#"{"name": "Box", "url": "ftp://packs.lan"}"#, sender()).is_none());
+        assert!(parse_reply(b"not json", sender()).is_none());
+    }
+}
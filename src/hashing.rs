@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crypto::digest::Digest;
+use crypto::sha1::Sha1;
+use crypto::sha2::{Sha256, Sha512};
+
+/// Hex encoded sha256 of a byte slice.
+pub fn sha256_bytes(bytes: &[u8]) -> String{
+    let mut hasher = Sha256::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// True for hashes in the form `sha256_bytes` produces: 64 lowercase hex characters.
+pub fn is_sha256(hash: &str) -> bool{
+    hash.len() == 64 && hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Hex encoded sha1 of a byte slice, modrinth packs list sha1 and sha512 for every file.
+pub fn sha1_bytes(bytes: &[u8]) -> String{
+    let mut hasher = Sha1::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Hex encoded sha512 of a byte slice.
+pub fn sha512_bytes(bytes: &[u8]) -> String{
+    let mut hasher = Sha512::new();
+    hasher.input(bytes);
+    hasher.result_str()
+}
+
+/// Hex encoded sha256 of a file, read in chunks so large jars are not loaded at once.
+pub fn sha256_file(path: &Path) -> Result<String, String>{
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("{0}: {err}", path.display())),
+    };
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) => return Err(format!("{0}: {err}", path.display())),
+        };
+        hasher.input(&buffer[..read]);
+    }
+    Ok(hasher.result_str())
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::fs;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::cache::{is_cached, read_cached};
+use crate::hashing::sha256_bytes;
+use crate::installed::InstalledFile;
+use crate::manifest::PackManifest;
+use crate::storage::{data_subdir, pack_dir, read_json, write_json};
+
+const HISTORY_FILE:&str = "history.json";
+/// Older revisions are dropped from the history.
+const MAX_REVISIONS:usize = 5;
+
+/// One installed state of a pack, kept so it can be reinstalled from the local cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackRevision{
+    pub manifest_hash:String,
+    pub installed_at:DateTime<Local>,
+    pub manifest:PackManifest,
+    pub files:Vec<InstalledFile>,
+}
+
+impl PackRevision{
+    pub fn new(manifest: &PackManifest, files: Vec<InstalledFile>) -> PackRevision{
+        PackRevision{
+            manifest_hash: manifest_hash(manifest),
+            installed_at: Local::now(),
+            manifest: manifest.clone(),
+            files,
+        }
+    }
+
+    pub fn short_hash(&self) -> &str{
+        &self.manifest_hash[..self.manifest_hash.len().min(8)]
+    }
+
+    /// Reads a file of this revision back from the local cache.
+    pub fn read_cached_file(&self, path: &str) -> Result<Vec<u8>, String>{
+        let file = match self.files.iter().find(|file| file.path == path) {
+            Some(file) => file,
+            None => return Err(format!("{path} is not part of revision {0}", self.short_hash())),
+        };
+        match read_cached(&file.sha256) {
+            Some(bytes) => Ok(bytes),
+            None => Err(format!("{path} is no longer in the local cache")),
+        }
+    }
+
+    /// Files of this revision that are missing from the local cache, it can only be
+    /// reinstalled when this is empty.
+    pub fn uncached_files(&self) -> Vec<&str>{
+        self.files.iter()
+            .filter(|file| !is_cached(&file.sha256))
+            .map(|file| file.path.as_str())
+            .collect()
+    }
+}
+
+pub fn manifest_hash(manifest: &PackManifest) -> String{
+    sha256_bytes(serde_json::to_string(manifest).unwrap_or_default().as_bytes())
+}
+
+/// Hashes of the files in the current revision of every pack, the cache keeps these so a
+/// pinned pack can always be reinstalled. Older revisions may be trimmed.
+pub fn current_revision_hashes() -> HashSet<String>{
+    let entries = match data_subdir("packs").map(fs::read_dir) {
+        Ok(Ok(entries)) => entries,
+        _ => return HashSet::new(),
+    };
+    entries.flatten()
+        .filter_map(|entry| read_json::<PackHistory>(&entry.path().join(HISTORY_FILE)).ok())
+        .filter_map(|history| history.current_revision().cloned())
+        .flat_map(|revision| revision.files)
+        .map(|file| file.sha256)
+        .collect()
+}
+
+/// Installed revisions of a pack, oldest first, and the revision the user pinned if any.
+/// A pinned pack is installed from the local cache instead of following the host.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PackHistory{
+    pub revisions:Vec<PackRevision>,
+    #[serde(default)]
+    pub pinned:Option<String>,
+}
+
+impl PackHistory{
+    pub fn load(pack_id: &str) -> PackHistory{
+        match pack_dir(pack_id) {
+            Ok(dir) => read_json(&dir.join(HISTORY_FILE)).unwrap_or_default(),
+            Err(_) => PackHistory::default(),
+        }
+    }
+
+    pub fn save(&self, pack_id: &str) -> Result<(), String>{
+        write_json(&pack_dir(pack_id)?.join(HISTORY_FILE), self)
+    }
+
+    pub fn pinned_revision(&self) -> Option<&PackRevision>{
+        let pinned = self.pinned.as_ref()?;
+        self.revisions.iter().find(|revision| revision.manifest_hash == *pinned)
+    }
+
+    /// The revision currently on disk, the pinned one or otherwise the newest.
+    pub fn current_revision(&self) -> Option<&PackRevision>{
+        self.pinned_revision().or(self.revisions.last())
+    }
+
+    /// The revision installed before the current one.
+    pub fn previous_revision(&self) -> Option<&PackRevision>{
+        let current = self.current_revision()?;
+        let index = self.revisions.iter().position(|revision| revision.manifest_hash == current.manifest_hash)?;
+        index.checked_sub(1).map(|index| &self.revisions[index])
+    }
+
+    /// Adds a freshly synced revision unless it is identical to the newest one, then drops
+    /// revisions past the limit.
+    pub fn record(&mut self, pack_id: &str, revision: PackRevision) -> Result<(), String>{
+        let unchanged = self.revisions.last()
+            .map(|last| last.manifest_hash == revision.manifest_hash && last.files.iter().map(|file| &file.sha256).eq(revision.files.iter().map(|file| &file.sha256)))
+            .unwrap_or(false);
+        if unchanged {
+            return Ok(());
+        }
+        self.revisions.retain(|existing| existing.manifest_hash != revision.manifest_hash);
+        self.revisions.push(revision);
+        while self.revisions.len() > MAX_REVISIONS {
+            self.revisions.remove(0);
+        }
+        self.save(pack_id)
+    }
+}
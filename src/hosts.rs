@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::http::HostAuthKind;
+use crate::local_pack::{is_valid_pack_id, LOCAL_PACK_PREFIX};
+use crate::storage::{data_dir, read_json, write_json};
+
+const HOSTS_FILE:&str = "hosts.json";
+pub(crate) const DEFAULT_HOST_URL:&str = "http://24.4.89.35:7878";
+
+/// A pack listed by a host in its `packs.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry{
+    pub id:String,
+    pub name:String,
+}
+
+impl CatalogEntry{
+    fn new(id: &str, name: &str) -> CatalogEntry{
+        CatalogEntry{ id: id.to_string(), name: name.to_string() }
+    }
+
+    /// The id names folders of the pack, so it follows the same rules as local pack ids
+    /// without ever taking the local prefix.
+    pub fn is_valid(&self) -> bool{
+        is_valid_pack_id(&self.id) && !self.id.to_lowercase().starts_with(LOCAL_PACK_PREFIX)
+    }
+}
+
+/// The packs every host served before hosts could list their own.
+pub fn default_catalog() -> Vec<CatalogEntry>{
+    vec![
+        CatalogEntry::new("ModTeam", "Modded Team Pack"),
+        CatalogEntry::new("Base", "Base Pack"),
+        CatalogEntry::new("Other1", "Other 1"),
+        CatalogEntry::new("Other2", "Other 2"),
+        CatalogEntry::new("Other3", "Other 3"),
+    ]
+}
+
+/// A named pack server the user saved, e.g. "home server" or "LAN test box".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostProfile{
+    /// Keeps the local state of packs apart between hosts.
+    #[serde(default = "new_host_id")]
+    pub id:String,
+    pub name:String,
+    pub url:String,
+    #[serde(default)]
+    pub cert_sha256:String,
+    #[serde(default)]
+    pub auth_kind:HostAuthKind,
+    #[serde(default)]
+    pub auth_username:String,
+    /// Kept in memory only, like the other credentials.
+    #[serde(skip)]
+    pub auth_secret:String,
+    /// Hosts serving the same packs, tried in order when this one fails.
+    #[serde(default)]
+    pub mirrors:Vec<String>,
+    /// The packs the host listed last time, so the selector works while it is offline.
+    #[serde(default = "default_catalog")]
+    pub catalog:Vec<CatalogEntry>,
+    /// Ed25519 public keys of the pack maintainers in hex. With keys set, manifests are only
+    /// installed when `manifest.json.sig` verifies against one of them.
+    #[serde(default)]
+    pub trusted_keys:Vec<String>,
+}
+
+impl HostProfile{
+    pub fn new(name: &str, url: &str) -> HostProfile{
+        HostProfile{
+            id: new_host_id(),
+            name: name.to_string(),
+            url: url.to_string(),
+            cert_sha256: "".to_string(),
+            auth_kind: HostAuthKind::None,
+            auth_username: "".to_string(),
+            auth_secret: "".to_string(),
+            mirrors: vec![],
+            catalog: default_catalog(),
+            trusted_keys: vec![],
+        }
+    }
+
+    pub fn base_url(&self) -> String{
+        self.url.trim().trim_end_matches('/').to_string()
+    }
+
+    pub fn mirror_urls(&self) -> Vec<String>{
+        self.mirrors.iter()
+            .map(|mirror| mirror.trim().trim_end_matches('/').to_string())
+            .filter(|mirror| !mirror.is_empty())
+            .collect()
+    }
+
+    /// Id of the local state of a pack from this host, e.g. `ModTeam@3f2a9c1e`.
+    pub fn pack_state_id(&self, pack_id: &str) -> String{
+        format!("{pack_id}@{0}", self.id)
+    }
+
+    pub fn trusted_key_list(&self) -> Vec<String>{
+        self.trusted_keys.iter()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .collect()
+    }
+}
+
+fn new_host_id() -> String{
+    Uuid::new_v4().to_simple().to_string()[..8].to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostProfiles{
+    pub hosts:Vec<HostProfile>,
+    #[serde(default)]
+    pub active:usize,
+}
+
+impl Default for HostProfiles{
+    fn default() -> Self{
+        HostProfiles{
+            hosts: vec![HostProfile::new("Default", DEFAULT_HOST_URL)],
+            active: 0,
+        }
+    }
+}
+
+impl HostProfiles{
+    pub fn load() -> HostProfiles{
+        let mut profiles: HostProfiles = match data_dir() {
+            Ok(dir) => read_json(&dir.join(HOSTS_FILE)).unwrap_or_default(),
+            Err(_) => HostProfiles::default(),
+        };
+        if profiles.hosts.is_empty() {
+            profiles = HostProfiles::default();
+        }
+        profiles.active = profiles.active.min(profiles.hosts.len() - 1);
+        for host in profiles.hosts.iter_mut() {
+            host.catalog.retain(|entry| entry.is_valid());
+        }
+        // host ids handed out while loading name the pack folders, so they have to stick
+        let _ = profiles.save();
+        profiles
+    }
+
+    pub fn save(&self) -> Result<(), String>{
+        write_json(&data_dir()?.join(HOSTS_FILE), self)
+    }
+
+    pub fn active(&self) -> &HostProfile{
+        &self.hosts[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut HostProfile{
+        &mut self.hosts[self.active]
+    }
+
+    pub fn add(&mut self, profile: HostProfile){
+        self.hosts.push(profile);
+        self.active = self.hosts.len() - 1;
+    }
+
+    /// Removes the active host, the last remaining host cannot be removed.
+    pub fn remove_active(&mut self) -> bool{
+        if self.hosts.len() <= 1 {
+            return false;
+        }
+        self.hosts.remove(self.active);
+        self.active = self.active.min(self.hosts.len() - 1);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CatalogEntry, HostProfile};
+
+    #[test]
+    fn catalog_ids_must_be_slugs() {
+        for id in ["ModTeam", "Other1", "all-the-mods-9"] {
+            assert!(CatalogEntry::new(id, id).is_valid(), "{id}");
+        }
+        for id in ["", "..", "../../instances", "Mod Team", "C:", "a/b", "a\\b", "-pack", "pack-", "local-pack", "ModTeam@1234"] {
+            assert!(!CatalogEntry::new(id, id).is_valid(), "{id}");
+        }
+    }
+
+    #[test]
+    fn pack_state_is_kept_apart_per_host() {
+        let first = HostProfile::new("Home", "https://home.example");
+        let second = HostProfile::new("Club", "https://club.example");
+        assert_ne!(first.pack_state_id("ModTeam"), second.pack_state_id("ModTeam"));
+        assert!(first.pack_state_id("ModTeam").starts_with("ModTeam@"));
+    }
+}
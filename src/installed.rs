@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use crate::storage::{pack_dir, read_json, write_json};
+
+const INSTALLED_FILE:&str = "installed.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledFile{
+    /// Path relative to the game folder with `/` separators, e.g. `mods/jei.jar`.
+    pub path:String,
+    /// sha256 of the pack's copy as it was last installed, used to tell if the user edited it.
+    pub sha256:String,
+}
+
+/// What the last successful sync of a pack put on disk.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct InstalledPack{
+    pub files:Vec<InstalledFile>,
+}
+
+impl InstalledPack{
+    /// Loads the record of a pack, a pack that was never synced has no files.
+    pub fn load(pack_id: &str) -> InstalledPack{
+        match pack_dir(pack_id) {
+            Ok(dir) => read_json(&dir.join(INSTALLED_FILE)).unwrap_or_default(),
+            Err(_) => InstalledPack::default(),
+        }
+    }
+
+    pub fn save(&self, pack_id: &str) -> Result<(), String>{
+        write_json(&pack_dir(pack_id)?.join(INSTALLED_FILE), self)
+    }
+
+    pub fn contains_mod(&self, jar_name: &str) -> bool{
+        let path = format!("mods/{jar_name}");
+        self.files.iter().any(|file| file.path == path)
+    }
+
+    pub fn installed_hash(&self, path: &str) -> Option<&str>{
+        self.files.iter().find(|file| file.path == path).map(|file| file.sha256.as_str())
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use crate::launch::LaunchSettings;
+use crate::storage::data_subdir;
+
+const LAUNCHER_PROFILES_FILE:&str = "launcher_profiles.json";
+/// Files copied from the shared install into a new instance so settings like keybinds carry over.
+const SEEDED_FILES:[&str; 1] = ["options.txt"];
+
+/// Every pack gets its own game directory (mods, config, saves, options.txt), while
+/// libraries, assets and versions stay shared in `.minecraft`.
+pub fn instance_dir(pack_id: &str) -> Result<PathBuf, String>{
+    data_subdir(&format!("instances/{pack_id}"))
+}
+
+pub fn instance_mods_dir(pack_id: &str) -> Result<PathBuf, String>{
+    Ok(instance_dir(pack_id)?.join("mods"))
+}
+
+pub fn launcher_profile_id(pack_id: &str) -> String{
+    format!("qmodmanager-{0}", pack_id.to_lowercase())
+}
+
+/// Creates the instance directory of the pack and points a launcher profile at it, so the
+/// launcher starts forge with `--gameDir` set to the instance. Returns the instance path.
+pub fn prepare_instance(minecraft_path: &Path, launch_settings: &LaunchSettings) -> Result<PathBuf, String>{
+    let instance_path = instance_dir(&launch_settings.state_id)?;
+    for dir in ["mods", "config", "saves"] {
+        if let Err(err) = fs::create_dir_all(instance_path.join(dir)) {
+            return Err(err.to_string());
+        }
+    }
+    for file in SEEDED_FILES {
+        let shared = minecraft_path.join(file);
+        let instance_file = instance_path.join(file);
+        if shared.exists() && !instance_file.exists() {
+            if let Err(err) = fs::copy(&shared, &instance_file) {
+                return Err(format!("{0}: {err}", shared.display()));
+            }
+        }
+    }
+    write_launcher_profile(minecraft_path, &instance_path, launch_settings)?;
+    Ok(instance_path)
+}
+
+fn write_launcher_profile(minecraft_path: &Path, instance_path: &Path, launch_settings: &LaunchSettings) -> Result<(), String>{
+    let profiles_path = minecraft_path.join(LAUNCHER_PROFILES_FILE);
+    let mut profiles: Value = match fs::read_to_string(&profiles_path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(profiles) => profiles,
+            Err(err) => return Err(format!("{0}: {err}", profiles_path.display())),
+        },
+        Err(_) => json!({}),
+    };
+    let root = match profiles.as_object_mut() {
+        Some(root) => root,
+        None => return Err(format!("{0} is not a json object", profiles_path.display())),
+    };
+    let entries = root.entry("profiles").or_insert_with(|| Value::Object(Map::new()));
+    let entries = match entries.as_object_mut() {
+        Some(entries) => entries,
+        None => return Err(format!("{0} has a malformed profiles list", profiles_path.display())),
+    };
+
+    let profile_id = launcher_profile_id(&launch_settings.state_id);
+    let now = Utc::now().to_rfc3339();
+    let created = entries.get(&profile_id)
+        .and_then(|profile| profile.get("created"))
+        .cloned()
+        .unwrap_or(Value::String(now.clone()));
+    entries.insert(profile_id, json!({
+        "name": format!("QModManager - {0}", launch_settings.modpack.get_name()),
+        "type": "custom",
+        "icon": "Furnace",
+        "created": created,
+        "lastUsed": now,
+        "lastVersionId": format!("{0}-forge-{1}", launch_settings.minecraft_version, launch_settings.forge_version),
+        "gameDir": instance_path.to_string_lossy(),
+    }));
+
+    let text = match serde_json::to_string_pretty(&profiles) {
+        Ok(text) => text,
+        Err(err) => return Err(err.to_string()),
+    };
+    match fs::write(&profiles_path, text) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{0}: {err}", profiles_path.display())),
+    }
+}
@@ -5,23 +5,37 @@ use std::process::Command;
 use std::{fs, thread};
 use ansi_term::Color::Red;
 use directories::{BaseDirs, ProjectDirs};
-use log::info;
+use log::debug;
+use crate::http::{HostAuth, HttpSettings};
 use crate::{App, Modpack};
 use crate::log::{error, info, warn};
-use crate::pack::{download_modpack};
+use crate::pack::{download_modpack, pack_url};
+use crate::installed::InstalledPack;
+use crate::instance::{launcher_profile_id, prepare_instance};
+use crate::manifest::PackManifest;
+use crate::mods::{check_dependencies, find_mod_issues, quarantine_jars, scan_mods_folder};
+use lazy_async_promise::{DataState, Promise};
 
+#[derive(Clone)]
 pub struct LaunchSettings{
     pub(crate) forge_version: String,
     pub(crate) minecraft_version: String,
 
     pub(crate) modpack: Modpack,
+    /// Key of the local state of the pack, see `Modpack::state_id`.
+    pub(crate) state_id: String,
+
+    pub(crate) quarantine_extras:bool,
+    pub(crate) cache_limit_mb:u64,
 
     pub(crate) is_cracked:bool,
     pub(crate) auth_username:String,
     pub(crate) auth_password:String,
 
-    pub(crate) host_ip:String,
-    pub(crate) host_port:i32,
+    pub(crate) host_url:String,
+    pub(crate) host_mirrors:Vec<String>,
+    pub(crate) http:HttpSettings,
+    pub(crate) trusted_keys:Vec<String>,
 }
 
 impl LaunchSettings{
@@ -30,11 +44,23 @@ impl LaunchSettings{
             forge_version:app.forge_version.clone(),
             minecraft_version:app.minecraft_version.clone(),
             modpack: app.modpack.clone(),
+            state_id: app.modpack.state_id(app.hosts.active()),
+            quarantine_extras: app.quarantine_extras,
+            cache_limit_mb: app.cache_limit_mb,
             is_cracked:app.is_cracked.clone(),
             auth_username: app.auth_username.clone(),
             auth_password: app.auth_password.clone(),
-            host_ip: app.host_ip.clone(),
-            host_port: app.host_port.clone(),
+            host_url: app.hosts.active().base_url(),
+            host_mirrors: app.hosts.active().mirror_urls(),
+            http: HttpSettings{
+                cert_sha256: app.hosts.active().cert_sha256.clone(),
+                auth: HostAuth{
+                    kind: app.hosts.active().auth_kind,
+                    username: app.hosts.active().auth_username.clone(),
+                    secret: app.hosts.active().auth_secret.clone(),
+                },
+            },
+            trusted_keys: app.hosts.active().trusted_key_list(),
         }
     }
 }
@@ -46,6 +72,7 @@ enum LaunchAbortReason{
     NoArgumentsFound,
     NetworkError,
     RuntimeException,
+    ModsIncompatible,
 }
 
 impl LaunchAbortReason {
@@ -57,6 +84,7 @@ impl LaunchAbortReason {
             LaunchAbortReason::NoArgumentsFound => "NoArgumentsFound",
             LaunchAbortReason::NetworkError => "NetworkError",
             LaunchAbortReason::RuntimeException => "RuntimeException",
+            LaunchAbortReason::ModsIncompatible => "ModsIncompatible",
         }
     }
 }
@@ -138,7 +166,18 @@ pub fn preform_launch_checks(app:&mut App,launch_settings: &LaunchSettings)->Res
 pub fn launch(app:&mut App,launch_settings: &LaunchSettings){
     match preform_launch_checks(app,launch_settings) {
         Ok((minecraft_path,fml_path,fml_jar)) => {
-            download_and_launch(app,launch_settings.modpack.clone(),minecraft_path,&launch_settings)
+            let instance_path = match prepare_instance(Path::new(&minecraft_path), launch_settings) {
+                Ok(path) => {
+                    info(format!("Using instance @ {0}",path.display()).as_str(), app);
+                    path.to_string_lossy().to_string()
+                }
+                Err(err) => {
+                    error(format!("Could not prepare the pack instance: {0}",err).as_str(), app);
+                    abort_launch(app, LaunchAbortReason::RuntimeException);
+                    return;
+                }
+            };
+            download_and_launch(app,launch_settings.modpack.clone(),instance_path,&launch_settings)
         }
         Err(_) => {
             error("Launch checks failed", app);
@@ -147,20 +186,18 @@ pub fn launch(app:&mut App,launch_settings: &LaunchSettings){
     }
 }
 
-fn download_and_launch(app:&mut App, modpack: Modpack, minecraft_path: String, launch_settings: &LaunchSettings){
+fn download_and_launch(app:&mut App, modpack: Modpack, instance_path: String, launch_settings: &LaunchSettings){
 
     info(&format!("begin request for {0}",modpack.get_name()),app);
 
-    let url = format!("http://{0}:{1}/{2}",launch_settings.host_ip,launch_settings.host_port,launch_settings.modpack.get_server_identifier());
+    let url = pack_url(launch_settings);
 
     info(&format!("url: {}", url),app);
 
-    fs::write(Path::new(crate::pack::TEMP_DATA_PATH), format!("{}\n{}", url, minecraft_path)).unwrap();
-
-    match download_modpack(app,modpack,minecraft_path,launch_settings){
+    match download_modpack(app,modpack,instance_path.clone(),launch_settings){
         Ok(_) => {
-            info("Download complete, launching client...", app);
-            launch_client(app)
+            info("Download started, the client launches once it completes", app);
+            app.pending_launch = Some((launch_settings.clone(), instance_path));
         }
         Err(_) => {
             error("Failed to download modpack", app);
@@ -168,7 +205,85 @@ fn download_and_launch(app:&mut App, modpack: Modpack, minecraft_path: String, l
     }
 }
 
-fn launch_client(app:&mut App) {
+/// Checks that every mod in the synced pack has its required dependencies, in the versions it
+/// asks for, so a broken pack fails here with a list instead of crashing forge at load.
+pub fn preform_mod_checks(app:&mut App, instance_path: &str, launch_settings: &LaunchSettings) -> Result<(),()>{
+    let installed = InstalledPack::load(&launch_settings.state_id);
+    let mods_path = Path::new(instance_path).join("mods");
+    let mut jars = match scan_mods_folder(&mods_path, &installed) {
+        Ok(jars) => jars,
+        Err(err) => {
+            error(format!("Could not read the mods folder: {0}",err).as_str(), app);
+            abort_launch(app, LaunchAbortReason::RuntimeException);
+            return Err(());
+        }
+    };
+
+    let issues = find_mod_issues(&jars, &PackManifest::load_installed(&launch_settings.state_id).conflicts);
+    for issue in &issues {
+        warn(issue.message.as_str(), app);
+    }
+    let extras = issues.into_iter().flat_map(|issue| issue.extras).collect::<Vec<PathBuf>>();
+    if launch_settings.quarantine_extras && !extras.is_empty() {
+        match quarantine_jars(&mods_path, &extras) {
+            Ok(moved) => info(format!("Moved {0} extra jars to mods-quarantine",moved).as_str(), app),
+            Err(err) => warn(format!("Could not quarantine extra jars: {0}",err).as_str(), app),
+        }
+        jars = match scan_mods_folder(&mods_path, &installed) {
+            Ok(jars) => jars,
+            Err(err) => {
+                error(format!("Could not read the mods folder: {0}",err).as_str(), app);
+                abort_launch(app, LaunchAbortReason::RuntimeException);
+                return Err(());
+            }
+        };
+    } else if !extras.is_empty() {
+        warn("Enable \"Quarantine Duplicate Mods\" or use the Mods tab to move the extra jars out of the way", app);
+    }
+
+    let problems = check_dependencies(&jars, &launch_settings.minecraft_version, &launch_settings.forge_version);
+    if !problems.is_empty() {
+        error(format!("Found {0} missing or incompatible mods:",problems.len()).as_str(), app);
+        for problem in problems {
+            error(format!(" - {0}",problem).as_str(), app);
+        }
+        abort_launch(app, LaunchAbortReason::ModsIncompatible);
+        return Err(());
+    }
+
+    info(format!("Mod checks passed for {0} jars",jars.len()).as_str(), app);
+    Ok(())
+}
+
+/// Launches the client once the download started by `download_and_launch` has finished.
+pub fn poll_pending_launch(app:&mut App){
+    if app.pending_launch.is_none() {
+        return;
+    }
+    let state = match &mut app.download_callback {
+        Some(callback) => callback.poll_state().clone(),
+        None => {
+            app.pending_launch = None;
+            return;
+        }
+    };
+    match state {
+        DataState::UpToDate => {
+            let (launch_settings, instance_path) = app.pending_launch.take().unwrap();
+            if preform_mod_checks(app, &instance_path, &launch_settings).is_ok() {
+                info("Download complete, launching client...", app);
+                launch_client(app, &launch_settings)
+            }
+        }
+        DataState::Error(_) => {
+            app.pending_launch = None;
+            abort_launch(app, LaunchAbortReason::NetworkError);
+        }
+        _ => {}
+    }
+}
+
+fn launch_client(app:&mut App, launch_settings: &LaunchSettings) {
     match Command::new("C:\\Program Files (x86)\\Minecraft Launcher\\MinecraftLauncher.exe").spawn() {
         Ok(_) => {
             info("Found minecraft in the default install location", app);
@@ -185,6 +300,9 @@ fn launch_client(app:&mut App) {
             }
         }
     }
+    // the launcher opens on whichever profile was used last
+    let profile_id = launcher_profile_id(&launch_settings.state_id);
+    info(format!("Select the \"QModManager - {0}\" profile ({profile_id}) in the launcher",launch_settings.modpack.get_name()).as_str(), app);
 }
 
 pub fn get_launch_command(app:&mut App, fml_path: &Path, fml_jar: &Path, launch_settings: &LaunchSettings) ->Result<String,()>{
@@ -216,7 +334,7 @@ pub fn get_launch_command(app:&mut App, fml_path: &Path, fml_jar: &Path, launch_
         jvm_args.push_str(&arg.to_string().replace("\"",""));
         jvm_args.push_str(" ");
     }
-    println!("{:?}", jvm_args);
+    debug!("jvm arguments: {jvm_args}");
     let mut jar_args = "".to_string();
     for arg in jar{
         jar_args.push_str(&arg.to_string().replace("\"",""));
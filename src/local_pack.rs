@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::hashing::sha256_bytes;
+use crate::manifest::{PackFile, PackManifest};
+use crate::storage::{data_subdir, read_json};
+
+pub(crate) const LOCAL_PACK_PREFIX:&str = "local-";
+
+/// The outcome of an import, `notes` lists everything that was skipped or adjusted.
+#[derive(Debug)]
+pub struct ImportReport{
+    pub pack_id:String,
+    pub name:String,
+    pub file_count:usize,
+    pub notes:Vec<String>,
+}
+
+/// Imported packs live in the data directory in the same layout a pack host serves:
+/// `manifest.json` next to the files at their remote paths, so they sync like any other pack.
+pub fn local_pack_dir(pack_id: &str) -> Result<PathBuf, String>{
+    data_subdir(&format!("local_packs/{pack_id}"))
+}
+
+pub fn load_local_manifest(pack_id: &str) -> Result<PackManifest, String>{
+    read_json(&local_pack_dir(pack_id)?.join("manifest.json"))
+}
+
+/// Ids of all imported packs, sorted by name.
+pub fn list_local_packs() -> Vec<String>{
+    let dir = match data_subdir("local_packs") {
+        Ok(dir) => dir,
+        Err(_) => return vec![],
+    };
+    let mut packs = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten()
+            .filter(|entry| entry.path().join("manifest.json").exists())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>(),
+        Err(_) => vec![],
+    };
+    packs.sort();
+    packs
+}
+
+/// Turns a pack name into an id that is safe as a folder name, prefixed so imported
+/// packs never share state with packs from a host.
+pub fn local_pack_id(name: &str) -> String{
+    let slug = name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    match slug.is_empty() {
+        true => format!("{LOCAL_PACK_PREFIX}pack"),
+        false => format!("{LOCAL_PACK_PREFIX}{slug}"),
+    }
+}
+
+/// True for ids `local_pack_id` could have produced from some name, apart from the prefix
+/// and letter case, so the id is safe as a folder name.
+pub fn is_valid_pack_id(id: &str) -> bool{
+    !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !id.starts_with('-')
+        && !id.ends_with('-')
+        && !id.contains("--")
+}
+
+/// Replaces the content of a local pack with the given files and manifest.
+pub fn write_local_pack(pack_id: &str, manifest: &PackManifest, files: &[(PackFile, Vec<u8>)]) -> Result<PathBuf, String>{
+    let dir = local_pack_dir(pack_id)?;
+    if let Err(err) = fs::remove_dir_all(&dir).and_then(|_| fs::create_dir_all(&dir)) {
+        return Err(format!("{0}: {err}", dir.display()));
+    }
+    for (file, bytes) in files {
+        // validates the path before anything is written
+        file.install_path()?;
+        let path = dir.join(file.remote_path());
+        if let Some(parent) = path.parent() {
+            if let Err(err) = fs::create_dir_all(parent) {
+                return Err(err.to_string());
+            }
+        }
+        if let Err(err) = fs::write(&path, bytes) {
+            return Err(format!("{0}: {err}", path.display()));
+        }
+    }
+    // record hashes so installs can reuse cached copies of the files
+    let mut manifest = manifest.clone();
+    for file in &mut manifest.files {
+        if let Some((_, bytes)) = files.iter().find(|(written, _)| written.remote_path() == file.remote_path()) {
+            file.sha256 = Some(sha256_bytes(bytes));
+            file.size = Some(bytes.len() as u64);
+        }
+    }
+    let text = match serde_json::to_string_pretty(&manifest) {
+        Ok(text) => text,
+        Err(err) => return Err(err.to_string()),
+    };
+    match fs::write(dir.join("manifest.json"), text) {
+        Ok(_) => Ok(dir),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -1,7 +1,15 @@
+use std::panic::Location;
+use std::path::Path;
 use crate::App;
-use chrono::Local;
+use crate::logfile::write_log_line;
+use crate::logger::is_level_enabled;
+use chrono::{DateTime, Local};
 use eframe::egui::Color32;
+use lazy_async_promise::{DataState, Promise};
 
+const MAX_CONSOLE_ENTRIES:usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LogLevel{
     Info,
     Warn,
@@ -18,34 +26,134 @@ impl LogLevel{
             LogLevel::Debug => "Debug",
         }
     }
+    pub fn color(&self) -> Color32{
+        match self {
+            LogLevel::Info => Color32::LIGHT_GRAY,
+            LogLevel::Warn => Color32::YELLOW,
+            LogLevel::Error => Color32::LIGHT_RED,
+            LogLevel::Debug => Color32::GRAY,
+        }
+    }
+    pub fn vec_all() -> Vec<LogLevel>{
+        vec![
+            LogLevel::Error,
+            LogLevel::Warn,
+            LogLevel::Info,
+            LogLevel::Debug,
+        ]
+    }
 }
 
 #[derive(Debug)]
 pub struct CallbackLog{
     pub data:String,
-    pub id:u32,
+    pub level:LogLevel,
 }
 
-pub fn format_message(message: String, level: LogLevel)->String{
-    let date = Local::now().format("%Y-%m-%d %H:%M:%S");
-    format!("{0} : [{1}] - {2}\n", date, level.as_str(), message)
+impl CallbackLog{
+    pub fn info(data: String) -> CallbackLog{
+        CallbackLog{ data, level: LogLevel::Info }
+    }
+    pub fn warn(data: String) -> CallbackLog{
+        CallbackLog{ data, level: LogLevel::Warn }
+    }
 }
 
+#[derive(Debug, Clone)]
+pub struct LogEntry{
+    pub timestamp:DateTime<Local>,
+    pub level:LogLevel,
+    pub source:String,
+    pub message:String,
+}
+
+impl LogEntry{
+    pub fn new(message: String, level: LogLevel, source: String) -> LogEntry{
+        LogEntry{ timestamp: Local::now(), level, source, message }
+    }
+    pub fn format_time(&self) -> String{
+        self.timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+    pub fn format_line(&self) -> String{
+        format!("{0} : [{1}] {2} - {3}\n", self.format_time(), self.level.as_str(), self.source, self.message)
+    }
+}
+
+/// Names the module a log call came from, `src/launch.rs` becomes `launch`.
+fn caller_source(location: &Location) -> String{
+    match Path::new(location.file()).file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => "app".to_string(),
+    }
+}
+
+/// Adds an entry to the console, entries above the configured verbosity are dropped.
+/// `write_file` is false for entries that already went through the logger.
+pub fn log_entry(entry: LogEntry, app: &mut App, write_file: bool){
+    if !is_level_enabled(entry.level) {
+        return;
+    }
+    if write_file {
+        write_log_line(&entry.format_line());
+    }
+    if app.log_entries.len() >= MAX_CONSOLE_ENTRIES {
+        app.log_entries.pop_front();
+    }
+    app.log_entries.push_back(entry);
+}
+
+#[track_caller]
 pub fn log_message(message: String, level: LogLevel,app: &mut App){
-    let date = Local::now().format("%Y-%m-%d %H:%M:%S");
-    let content = format!("{0} : [{1}] - {2}\n", date, level.as_str(), message);
-    app.debug_console_content.push_str(content.as_str());
+    let source = caller_source(Location::caller());
+    log_entry(LogEntry::new(message, level, source), app, true);
+}
+
+/// Moves every message the download task produced since the last call into the console.
+/// The promise keeps all messages in send order, so the cursor is the sequence number of the
+/// next unseen message and each one is delivered exactly once, no matter how many arrive
+/// between two repaints. An error raised by `unpack_result!` is reported once as well, and the
+/// mod list is rescanned once the sync is done.
+pub fn poll_callback_logs(app: &mut App){
+    let callback = match &mut app.download_callback {
+        Some(callback) => callback,
+        None => return,
+    };
+    let state = callback.poll_state().clone();
+    let new_logs = callback.as_slice()
+        .iter()
+        .skip(app.callback_log_cursor)
+        .map(|log| LogEntry::new(log.data.clone(), log.level, "pack".to_string()))
+        .collect::<Vec<LogEntry>>();
+
+    app.callback_log_cursor += new_logs.len();
+    for entry in new_logs {
+        log_entry(entry, app, true);
+    }
+
+    if app.callback_finished || !matches!(state, DataState::UpToDate | DataState::Error(_)) {
+        return;
+    }
+    app.callback_finished = true;
+    // rescan the mods folder the sync just changed, also after a failed sync
+    app.mod_list = None;
+    if let DataState::Error(err) = state {
+        log_entry(LogEntry::new(format!("Download failed: {err}"), LogLevel::Error, "pack".to_string()), app, true);
+    }
 }
 
+#[track_caller]
 pub fn info(message: &str, app: &mut App){
     log_message(message.to_string(), LogLevel::Info,app);
 }
+#[track_caller]
 pub fn warn(message: &str,app: &mut App){
     log_message(message.to_string(), LogLevel::Warn,app);
 }
+#[track_caller]
 pub fn error(message: &str,app: &mut App){
     log_message(message.to_string(), LogLevel::Error,app);
 }
+#[track_caller]
 pub fn debug(message: &str,app: &mut App){
     log_message(message.to_string(), LogLevel::Debug,app);
 }
\ No newline at end of file
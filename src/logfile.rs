@@ -0,0 +1,119 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::Local;
+use lazy_static::lazy_static;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+use crate::storage::data_subdir;
+
+const LOG_FILE_NAME:&str = "qmodmanager";
+const MAX_LOG_FILE_SIZE:u64 = 1024 * 1024;
+const MAX_LOG_FILES:usize = 5;
+
+struct LogFile{
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+}
+
+/// `qmodmanager.log` is the live file, older ones are `qmodmanager.1.log` (newest) up to
+/// `qmodmanager.4.log` (oldest).
+fn log_file_path(dir: &Path, index: usize) -> PathBuf{
+    match index {
+        0 => dir.join(format!("{LOG_FILE_NAME}.log")),
+        _ => dir.join(format!("{LOG_FILE_NAME}.{index}.log")),
+    }
+}
+
+fn open_log_file(dir: &Path) -> std::io::Result<LogFile>{
+    let file = OpenOptions::new().create(true).append(true).open(log_file_path(dir, 0))?;
+    let size = file.metadata()?.len();
+    Ok(LogFile{ dir: dir.to_path_buf(), file, size })
+}
+
+fn rotate(log_file: &mut LogFile) -> std::io::Result<()>{
+    let _ = fs::remove_file(log_file_path(&log_file.dir, MAX_LOG_FILES - 1));
+    for index in (0..MAX_LOG_FILES - 1).rev() {
+        let from = log_file_path(&log_file.dir, index);
+        if from.exists() {
+            fs::rename(from, log_file_path(&log_file.dir, index + 1))?;
+        }
+    }
+    *log_file = open_log_file(&log_file.dir)?;
+    Ok(())
+}
+
+/// Opens the log file in the data directory and writes a session header, every
+/// following `write_log_line` call is appended to it.
+pub fn start_log_session(header: &[String]) -> Result<(), String>{
+    let dir = data_subdir("logs")?;
+    let log_file = match open_log_file(&dir) {
+        Ok(log_file) => log_file,
+        Err(err) => return Err(err.to_string()),
+    };
+    *LOG_FILE.lock().unwrap() = Some(log_file);
+
+    write_log_line(&format!("===== session started {0} =====\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+    for line in header {
+        write_log_line(&format!("{line}\n"));
+    }
+    Ok(())
+}
+
+/// Appends a formatted line to the log file, rotating it once it grows past the size limit.
+/// Does nothing if no session was started, logging must never take the app down.
+pub fn write_log_line(line: &str){
+    let mut guard = match LOG_FILE.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let log_file = match guard.as_mut() {
+        Some(log_file) => log_file,
+        None => return,
+    };
+    if log_file.size + line.len() as u64 > MAX_LOG_FILE_SIZE && rotate(log_file).is_err() {
+        return;
+    }
+    if log_file.file.write_all(line.as_bytes()).is_ok() {
+        log_file.size += line.len() as u64;
+    }
+}
+
+/// Bundles all rotated log files into a zip in `out_dir` and returns its path.
+pub fn export_logs(out_dir: &Path) -> Result<PathBuf, String>{
+    let dir = data_subdir("logs")?;
+    let zip_path = out_dir.join(format!("QModManager-logs-{0}.zip", Local::now().format("%Y%m%d-%H%M%S")));
+    let zip_file = match File::create(&zip_path) {
+        Ok(file) => file,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    // hold the lock so the live file is not rotated halfway through the export
+    let _guard = LOG_FILE.lock();
+    let mut zip = ZipWriter::new(zip_file);
+    for index in 0..MAX_LOG_FILES {
+        let path = log_file_path(&dir, index);
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if let Err(err) = zip.start_file(name, SimpleFileOptions::default()) {
+            return Err(err.to_string());
+        }
+        if let Err(err) = zip.write_all(&content) {
+            return Err(err.to_string());
+        }
+    }
+    match zip.finish() {
+        Ok(_) => Ok(zip_path),
+        Err(err) => Err(err.to_string()),
+    }
+}
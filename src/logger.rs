@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+use eframe::egui;
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use crate::App;
+use crate::log::{log_entry, LogEntry, LogLevel};
+use crate::logfile::write_log_line;
+
+/// Backend for the `log` crate facade, so `log::info!` calls from our code and from
+/// dependencies like reqwest or zip end up in the console and the log file.
+struct ConsoleLogger;
+
+lazy_static! {
+    static ref PENDING_ENTRIES: Mutex<Vec<LogEntry>> = Mutex::new(vec![]);
+    static ref REPAINT_CTX: Mutex<Option<egui::Context>> = Mutex::new(None);
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Error => LogLevel::Error,
+            Level::Warn => LogLevel::Warn,
+            Level::Info => LogLevel::Info,
+            Level::Debug | Level::Trace => LogLevel::Debug,
+        };
+        let entry = LogEntry::new(record.args().to_string(), level, record.target().to_string());
+        // the file is written right away, the console picks the entry up on the next frame
+        write_log_line(&entry.format_line());
+        if let Ok(mut pending) = PENDING_ENTRIES.lock() {
+            pending.push(entry);
+        }
+        if let Ok(ctx) = REPAINT_CTX.lock() {
+            if let Some(ctx) = ctx.as_ref() {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init_logger(verbosity: LevelFilter){
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(verbosity);
+    }
+}
+
+pub fn set_repaint_context(ctx: &egui::Context){
+    if let Ok(mut repaint_ctx) = REPAINT_CTX.lock() {
+        if repaint_ctx.is_none() {
+            *repaint_ctx = Some(ctx.clone());
+        }
+    }
+}
+
+pub fn set_verbosity(verbosity: LevelFilter){
+    log::set_max_level(verbosity);
+}
+
+/// Maps our levels onto the facade so the console and third-party logs share one verbosity.
+pub fn is_level_enabled(level: LogLevel) -> bool{
+    let level = match level {
+        LogLevel::Error => Level::Error,
+        LogLevel::Warn => Level::Warn,
+        LogLevel::Info => Level::Info,
+        LogLevel::Debug => Level::Debug,
+    };
+    level <= log::max_level()
+}
+
+/// Moves the records collected by the logger since the last frame into the console.
+pub fn poll_logger_entries(app: &mut App){
+    let entries = match PENDING_ENTRIES.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+    for entry in entries {
+        log_entry(entry, app, false);
+    }
+}
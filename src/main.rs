@@ -5,20 +5,68 @@ mod log;
 mod launch;
 mod auth;
 mod pack;
+mod storage;
+mod logfile;
+mod console;
+mod logger;
+mod installed;
+mod mods;
+mod versions;
+mod hashing;
+mod manifest;
+mod selection;
+mod instance;
+mod local_pack;
+mod mrpack;
+mod curseforge;
+mod history;
+mod cache;
+mod update_check;
+mod http;
+mod signing;
+mod hosts;
+mod discovery;
+mod throttle;
+#[cfg(test)]
+mod test_server;
 
+use std::collections::VecDeque;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::ops::Add;
 use winresource::WindowsResource;
 use std::process::Command;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use directories::UserDirs;
 use eframe::{egui, NativeOptions, WindowBuilderHook};
-use eframe::egui::{popup_below_widget, CentralPanel, DragValue, Id, InnerResponse, PopupCloseBehavior, Response, ScrollArea, SidePanel, TopBottomPanel, Ui, IconData, Layout, Align, ProgressBar};
+use eframe::egui::{popup_below_widget, CentralPanel, DragValue, Id, InnerResponse, PopupCloseBehavior, Response, ScrollArea, SidePanel, TopBottomPanel, Ui, IconData, Layout, Align, ProgressBar, RichText, Color32};
 use eframe::egui::Key::P;
-use lazy_async_promise::{DataState, DirectCacheAccess, LazyVecPromise, Progress, Promise};
+use lazy_async_promise::{ImmediateValuePromise, ImmediateValueState, DataState, DirectCacheAccess, LazyVecPromise, Progress, Promise};
 use lazy_static::lazy_static;
-use crate::launch::{launch, preform_launch_checks, verify_fml_folder, verify_minecraft_install, LaunchSettings};
-use crate::log::{error, format_message, info, CallbackLog, LogLevel};
-use crate::pack::{download_modpack, setup_temp_folder};
+use ::log::LevelFilter;
+use crate::launch::{launch, poll_pending_launch, preform_launch_checks, verify_fml_folder, verify_minecraft_install, LaunchSettings};
+use crate::console::{console_panel, ConsoleFilter};
+use crate::log::{error, info, poll_callback_logs, warn, CallbackLog, LogEntry};
+use crate::logfile::{export_logs, start_log_session};
+use crate::logger::{init_logger, poll_logger_entries, set_repaint_context, set_verbosity};
+use crate::installed::InstalledPack;
+use crate::history::PackHistory;
+use crate::cache::{cache_size, clear_cache, DEFAULT_CACHE_LIMIT_MB};
+use crate::curseforge::{import_curseforge, CurseForgeApiResolver};
+use crate::local_pack::{list_local_packs, load_local_manifest, ImportReport};
+use crate::mrpack::{export_mrpack, import_mrpack};
+use crate::instance::{instance_dir, instance_mods_dir, prepare_instance};
+use crate::manifest::{export_server_file_list, PackManifest};
+use crate::mods::{find_mod_issues, quarantine_jars, scan_mods_folder, ModIssue, ModJar};
+use crate::pack::{download_modpack, setup_temp_folder, start_catalog_fetch, start_manifest_fetch, start_update_check};
+use crate::update_check::UpdateStatus;
+use crate::http::{describe_proxy, is_plain_http, set_proxy, HostAuthKind, NetworkSettings};
+use crate::hosts::{CatalogEntry, HostProfile, HostProfiles};
+use crate::discovery::{discover_hosts, DiscoveredHost};
+use crate::throttle::set_download_limit;
+use crate::selection::ModSelection;
+use crate::storage::data_dir;
 
 const WIDTH:f32  = 1000.;
 const HEIGHT:f32  = 700.;
@@ -26,6 +74,18 @@ const VERSION:&str = "QModManager - V2.0.0";
 
 #[tokio::main]
 async fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() == 4 && args[1] == "--server-files" {
+        match export_server_file_list(Path::new(&args[2]), Path::new(&args[3])) {
+            Ok(count) => println!("wrote {count} server files to {0}", args[3]),
+            Err(err) => {
+                eprintln!("could not export server files: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     match setup_temp_folder(){
         Ok(_)=>{}
         Err(_)=>{}
@@ -56,36 +116,60 @@ enum Modpack{
     Other1,
     Other2,
     Other3,
+    /// A pack imported from a file, see `local_pack.rs`
+    Local(String),
+    /// A pack listed in the catalog of a host, see `hosts.rs`
+    Hosted(CatalogEntry),
 }
 
 impl Modpack {
-    fn get_server_identifier(&self) -> &'static str {
+    fn get_server_identifier(&self) -> &str {
         match self {
             Modpack::ModTeam => "ModTeam",
             Modpack::Base => "Base",
             Modpack::Other1 => "Other1",
             Modpack::Other2 => "Other2",
             Modpack::Other3 => "Other3",
+            Modpack::Local(id) => id,
+            Modpack::Hosted(entry) => &entry.id,
         }
     }
-    fn get_name(&self) -> &'static str {
+    fn get_name(&self) -> String {
         match self {
-            Modpack::ModTeam => "Modded Team Pack",
-            Modpack::Base => "Base Pack",
-            Modpack::Other1 => "Other 1",
-            Modpack::Other2 => "Other 2",
-            Modpack::Other3 => "Other 3",
+            Modpack::ModTeam => "Modded Team Pack".to_string(),
+            Modpack::Base => "Base Pack".to_string(),
+            Modpack::Other1 => "Other 1".to_string(),
+            Modpack::Other2 => "Other 2".to_string(),
+            Modpack::Other3 => "Other 3".to_string(),
+            Modpack::Local(id) => format!("{0} (local)", id.trim_start_matches("local-")),
+            Modpack::Hosted(entry) => entry.name.clone(),
         }
     }
-    fn vec_all() -> Vec<Modpack>{
-        vec![
-            Modpack::ModTeam,
-            Modpack::Base,
-            Modpack::Other1,
-            Modpack::Other2,
-            Modpack::Other3,
-        ]
+    /// Id of the local state of the pack (instance, installed files, history, selection).
+    /// Packs from a host are kept apart per host, local packs do not belong to one.
+    fn state_id(&self, host: &HostProfile) -> String {
+        match self {
+            Modpack::Local(id) => id.clone(),
+            modpack => host.pack_state_id(modpack.get_server_identifier()),
+        }
     }
+    /// Maps catalog entries onto the built in packs, so their local state carries over.
+    fn from_catalog(entry: &CatalogEntry) -> Modpack {
+        match entry.id.as_str() {
+            "ModTeam" => Modpack::ModTeam,
+            "Base" => Modpack::Base,
+            "Other1" => Modpack::Other1,
+            "Other2" => Modpack::Other2,
+            "Other3" => Modpack::Other3,
+            _ => Modpack::Hosted(entry.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum CenterTab{
+    Console,
+    Mods,
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -96,37 +180,83 @@ struct App {
     game: Game,
 
     modpack: Modpack,
+    remote_manifest:Option<ImmediateValuePromise<Result<PackManifest,String>>>,
+    update_check:Option<ImmediateValuePromise<Result<UpdateStatus,String>>>,
+    update_checked_at:Instant,
+    update_status:Option<UpdateStatus>,
+    mod_selection:ModSelection,
+    pack_history:Option<PackHistory>,
+    pack_file_path:String,
+    curseforge_api_key:String,
+    import_task:Option<ImmediateValuePromise<Result<ImportReport,String>>>,
     minecraft_version:String,
     forge_version:String,
 
     is_cracked:bool,
 
-    host_ip:String,
-    host_port:i32,
+    hosts:HostProfiles,
+    catalog_fetch:Option<ImmediateValuePromise<Result<Vec<CatalogEntry>,String>>>,
+    catalog_fresh:bool,
+    lan_discovery:Option<ImmediateValuePromise<Result<Vec<DiscoveredHost>,String>>>,
 
     auth_username:String,
     auth_password:String,
     
-    debug_console_content:String,
-    prev_log_ids:Vec<u32>,
+    log_entries:VecDeque<LogEntry>,
+    console_filter:ConsoleFilter,
+    log_verbosity:LevelFilter,
+
+    center_tab:CenterTab,
+    mod_list:Option<Result<Vec<ModJar>,String>>,
+    mod_issues:Vec<ModIssue>,
+    quarantine_extras:bool,
+    cache_limit_mb:u64,
+    download_limit_kib:u32,
+    proxy_url:String,
+    callback_log_cursor:usize,
+    callback_finished:bool,
+    pending_launch:Option<(LaunchSettings,String)>,
 }
 
 impl Default for App {
     fn default() -> Self {
+        let hosts = HostProfiles::load();
         Self {
             update_callback_ctx: None,
             download_callback: None,
             game: Game::Minecraft,
             modpack: Modpack::ModTeam,
+            remote_manifest: None,
+            update_check: None,
+            update_checked_at: Instant::now(),
+            update_status: None,
+            mod_selection: ModSelection::load(&Modpack::ModTeam.state_id(hosts.active())),
+            pack_history: None,
+            pack_file_path: "".to_string(),
+            curseforge_api_key: "".to_string(),
+            import_task: None,
             minecraft_version: "1.20.1".to_string(),
             forge_version: "47.3.10".to_string(),
             is_cracked: false,
-            host_ip: "24.4.89.35".to_string(),
-            host_port: 7878,
+            hosts,
+            catalog_fetch: None,
+            catalog_fresh: false,
+            lan_discovery: None,
             auth_username: "".to_string(),
             auth_password: "Mine2021!".to_string(),
-            debug_console_content: "".to_string(),
-            prev_log_ids: vec![],
+            log_entries: VecDeque::new(),
+            console_filter: ConsoleFilter::default(),
+            log_verbosity: LevelFilter::Info,
+            center_tab: CenterTab::Console,
+            mod_list: None,
+            mod_issues: vec![],
+            quarantine_extras: false,
+            cache_limit_mb: DEFAULT_CACHE_LIMIT_MB,
+            download_limit_kib: 0,
+            proxy_url: NetworkSettings::load().proxy_url,
+            callback_log_cursor: 0,
+            callback_finished: false,
+            pending_launch: None,
         }
     }
 }
@@ -138,7 +268,24 @@ impl App {
         // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
         // for e.g. egui::PaintCallback.
         cc.egui_ctx.set_visuals(egui::Visuals::dark());
-        Self::default()
+        let mut app = Self::default();
+        init_logger(app.log_verbosity);
+        // before the session header names the proxy and before the first request
+        if let Err(err) = set_proxy(&app.proxy_url) {
+            error(&format!("Could not use the saved proxy, using the system proxy: {err}"), &mut app);
+        }
+        if let Err(err) = start_log_session(&app.session_header()) {
+            error(&format!("Could not open the log file: {err}"), &mut app);
+        }
+        app
+    }
+    fn session_header(&self) -> Vec<String> {
+        vec![
+            format!("{VERSION} ({0} {1})", env::consts::OS, env::consts::ARCH),
+            format!("game: {0:?}, minecraft: {1}, forge: {2}", self.game, self.minecraft_version, self.forge_version),
+            format!("modpack: {0}, host: {1} ({2})", self.modpack.get_server_identifier(), self.hosts.active().name, self.hosts.active().url),
+            format!("proxy: {0}", describe_proxy()),
+        ]
     }
     fn update_callback(&self) -> impl Fn() {
         let ctx = self.update_callback_ctx.clone().unwrap();
@@ -150,6 +297,13 @@ impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         let ctx_clone = ctx.clone();
         self.update_callback_ctx = Some(ctx_clone);
+        set_repaint_context(ctx);
+        poll_logger_entries(self);
+        poll_callback_logs(self);
+        poll_pending_launch(self);
+        poll_import_task(self);
+        poll_update_check(self);
+        poll_catalog_fetch(self);
 
         CentralPanel::default().show(ctx, |ui| {
             TopBottomPanel::top("top_panel")
@@ -191,9 +345,7 @@ impl eframe::App for App {
                 });
 
             CentralPanel::default().show_inside(ui, |ui| {
-                ScrollArea::vertical().show(ui, |ui| {
-                    center_panel(ui,self);
-                });
+                center_panel(ui,self);
             });
 
         });
@@ -239,51 +391,105 @@ fn left_panel(ui: &mut Ui, app: &mut App){
 }
 
 fn center_panel(ui: &mut Ui, app: &mut App){
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut app.center_tab, CenterTab::Console, "Console");
+        ui.selectable_value(&mut app.center_tab, CenterTab::Mods, "Mods");
+    });
+    ui.separator();
     ui.with_layout(Layout::bottom_up(Align::LEFT),|ui| {
         ui.label(VERSION);
-        ui.label(format!("{}",app.debug_console_content));
-    });
-    let mut new_logs = match &mut app.download_callback {
-        Some(callback) => {
-            match callback.poll_state() {
-                DataState::Updating(_)=> {
-                    let logs = callback.get_value();
-                    match logs {
-                        None => { None }
-                        Some(s) => {
-                            if s.len() < 1 {
-                                None
-                            } else {
-                                Some(s)
-                            }
-                        }
-                    }
-                }
-                _ => {None}
+        ui.with_layout(Layout::top_down(Align::LEFT),|ui| {
+            match app.center_tab {
+                CenterTab::Console => console_panel(ui,app),
+                CenterTab::Mods => mods_panel(ui,app),
             }
+        });
+    });
+}
+
+fn refresh_mod_list(app: &mut App){
+    let pack_id = app.modpack.state_id(app.hosts.active());
+    let installed = InstalledPack::load(&pack_id);
+    app.mod_list = Some(instance_mods_dir(&pack_id).and_then(|path| scan_mods_folder(&path, &installed)));
+    app.mod_issues = match &app.mod_list {
+        Some(Ok(jars)) => find_mod_issues(jars, &PackManifest::load_installed(&pack_id).conflicts),
+        _ => vec![],
+    };
+}
+
+fn quarantine_mod_extras(app: &mut App){
+    let extras = app.mod_issues.iter().flat_map(|issue| issue.extras.clone()).collect::<Vec<PathBuf>>();
+    let mods_path = match instance_mods_dir(&app.modpack.state_id(app.hosts.active())) {
+        Ok(path) => path,
+        Err(err) => {
+            error(&format!("Could not find the instance folder: {err}"), app);
+            return;
+        }
+    };
+    match quarantine_jars(&mods_path, &extras) {
+        Ok(moved) => info(&format!("Moved {moved} extra jars to mods-quarantine"), app),
+        Err(err) => error(&format!("Could not quarantine extra jars: {err}"), app),
+    }
+    refresh_mod_list(app);
+}
+
+fn mods_panel(ui: &mut Ui, app: &mut App){
+    if app.mod_list.is_none() {
+        refresh_mod_list(app);
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Refresh").clicked() {
+            refresh_mod_list(app);
+        }
+        if let Some(Ok(jars)) = &app.mod_list {
+            let from_pack = jars.iter().filter(|jar| jar.from_pack).count();
+            ui.label(format!("{0} jars, {1} from {2}, {3} local extras", jars.len(), from_pack, app.modpack.get_name(), jars.len() - from_pack));
         }
-        None => {
-            None
+    });
+    ui.separator();
+
+    if !app.mod_issues.is_empty() {
+        for issue in &app.mod_issues {
+            ui.label(RichText::new(&issue.message).color(Color32::YELLOW));
+        }
+        if app.mod_issues.iter().any(|issue| !issue.extras.is_empty()) && ui.button("Quarantine Extras").clicked() {
+            quarantine_mod_extras(app);
+        }
+        ui.separator();
+    }
+
+    let jars = match &app.mod_list {
+        Some(Ok(jars)) => jars,
+        Some(Err(err)) => {
+            ui.label(RichText::new(format!("Could not read the mods folder: {err}")).color(Color32::LIGHT_RED));
+            return;
         }
+        None => return,
     };
-    match new_logs {
-        None => {}
-        Some(logs) => {
-            let log = logs.last().unwrap();
-            if !app.prev_log_ids.contains(&log.id) {
-                app.debug_console_content.push_str(format_message(log.data.clone(),LogLevel::Info).as_str());
-                app.prev_log_ids.append(&mut vec![log.id]);
-                match &mut app.download_callback {
-                    Some(callback) => {
-                        let mut val = callback.get_value_mut();
-                        val = Some(&mut vec![]);
+    ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+        for jar in jars {
+            let source = match jar.from_pack {
+                true => RichText::new("pack").color(Color32::LIGHT_GREEN),
+                false => RichText::new("local").color(Color32::YELLOW),
+            };
+            ui.horizontal(|ui| {
+                ui.label(source);
+                ui.strong(&jar.file_name);
+            });
+            if let Some(err) = &jar.error {
+                ui.label(RichText::new(err).weak());
+            }
+            for info in &jar.mods {
+                ui.indent(&info.mod_id, |ui| {
+                    ui.label(format!("{0} ({1}) - {2}", info.display_name, info.mod_id, info.version));
+                    if !info.description.is_empty() {
+                        ui.label(RichText::new(&info.description).weak());
                     }
-                    _ => {}
-                }
+                });
             }
+            ui.separator();
         }
-    }
-
+    });
 }
 
 fn right_panel(ui: &mut Ui, app: &mut App){
@@ -293,24 +499,23 @@ fn right_panel(ui: &mut Ui, app: &mut App){
 
         line_break(ui);
 
-        ui.label("Host IP:");
-        ui.text_edit_singleline(&mut app.host_ip);
+        host_section(ui, app);
 
-        ui.label("Host Port:");
-        ui.add(
-            DragValue::new(&mut app.host_port)
-            .range(0..=65535)
-        );
+        lan_hosts_section(ui, app);
 
         line_break(ui);
 
-        egui::ComboBox::from_label("Modpack")
-            .selected_text(format!("{0:?}",app.modpack.get_name()))
-            .show_ui(ui, |ui| {
-                for pack in Modpack::vec_all() {
-                    ui.selectable_value(&mut app.modpack,pack.clone(), pack.get_name());
-                }
-            });
+        modpack_selector(ui, app);
+
+        update_status_section(ui, app);
+
+        line_break(ui);
+
+        optional_mods_section(ui, app);
+
+        line_break(ui);
+
+        history_section(ui, app);
 
         line_break(ui);
 
@@ -318,16 +523,98 @@ fn right_panel(ui: &mut Ui, app: &mut App){
 
         if ui.button("Open Game Folder").clicked(){
             Command::new( "explorer" )
-                .arg(verify_minecraft_install().unwrap())
+                .arg(instance_dir(&app.modpack.state_id(app.hosts.active())).unwrap())
                 .spawn( )
                 .unwrap( );
         }
 
         if ui.button("Download Modpack Raw").clicked(){
-            match download_modpack(app,app.modpack.clone(),verify_minecraft_install().unwrap(),&LaunchSettings::from_app(app)) {
-                Ok(_) => {}
+            download_to_instance(app);
+        }
+
+        ui.checkbox(&mut app.quarantine_extras, "Quarantine Duplicate Mods");
+
+        ui.horizontal(|ui| {
+            ui.label("Cache Limit (MB)");
+            ui.add(DragValue::new(&mut app.cache_limit_mb).range(256..=65536).speed(64));
+        });
+        ui.label("Proxy");
+        let response = ui.add(egui::TextEdit::singleline(&mut app.proxy_url).hint_text("system proxy, or socks5://host:port"));
+        if response.lost_focus() {
+            match set_proxy(&app.proxy_url) {
+                Ok(_) => {
+                    info(&format!("Using proxy: {0}", describe_proxy()), app);
+                    app.remote_manifest = None;
+                    app.update_check = None;
+                    app.catalog_fresh = false;
+                    let settings = NetworkSettings{ proxy_url: app.proxy_url.trim().to_string() };
+                    if let Err(err) = settings.save() {
+                        error(&format!("Could not save the proxy: {err}"), app);
+                    }
+                }
+                Err(err) => error(&format!("Could not use the proxy: {err}"), app),
+            }
+        }
+
+        let response = ui.horizontal(|ui| {
+            ui.label("Download Limit (KiB/s)");
+            ui.add(DragValue::new(&mut app.download_limit_kib).range(0..=1_000_000).speed(64))
+                .on_hover_text("0 downloads at full speed")
+        }).inner;
+        if response.changed() {
+            set_download_limit(app.download_limit_kib);
+        }
+        if response.drag_stopped() || response.lost_focus() {
+            match app.download_limit_kib {
+                0 => info("Download limit turned off", app),
+                limit => info(&format!("Downloads limited to {limit} KiB/s"), app),
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Clear Cache").clicked() {
+                match clear_cache() {
+                    Ok(freed) => info(&format!("Cleared {0} MB from the download cache", freed / 1024 / 1024), app),
+                    Err(err) => error(&format!("Failed to clear the download cache: {err}"), app),
+                }
+            }
+            if ui.small_button("Size").clicked() {
+                match cache_size() {
+                    Ok(size) => info(&format!("The download cache holds {0} MB of {1} MB", size / 1024 / 1024, app.cache_limit_mb), app),
+                    Err(err) => error(&format!("Failed to read the download cache: {err}"), app),
+                }
+            }
+        });
+
+        let prev_verbosity = app.log_verbosity;
+        egui::ComboBox::from_label("Log Verbosity")
+            .selected_text(app.log_verbosity.as_str())
+            .show_ui(ui, |ui| {
+                for verbosity in LevelFilter::iter() {
+                    ui.selectable_value(&mut app.log_verbosity, verbosity, verbosity.as_str());
+                }
+            });
+        if app.log_verbosity != prev_verbosity {
+            set_verbosity(app.log_verbosity);
+            info(&format!("Log verbosity set to {0}", app.log_verbosity),app);
+        }
+
+        line_break(ui);
+
+        pack_files_section(ui, app);
+
+        line_break(ui);
+
+        if ui.button("Export Logs").clicked(){
+            let out_dir = match UserDirs::new().and_then(|dirs| dirs.desktop_dir().map(|dir| dir.to_path_buf())) {
+                Some(dir) => Ok(dir),
+                None => data_dir(),
+            };
+            match out_dir.and_then(|dir| export_logs(&dir)) {
+                Ok(path) => {
+                    info(&format!("Exported logs to {0}", path.display()),app)
+                }
                 Err(err) => {
-                    error(&format!("Failed to download modpack: {err}"),app)
+                    error(&format!("Failed to export logs: {err}"),app)
                 }
             }
         }
@@ -335,6 +622,500 @@ fn right_panel(ui: &mut Ui, app: &mut App){
     });
 }
 
+const UPDATE_CHECK_INTERVAL:Duration = Duration::from_secs(10 * 60);
+
+fn download_to_instance(app: &mut App){
+    let launch_settings = LaunchSettings::from_app(app);
+    let instance_path = match verify_minecraft_install() {
+        Ok(minecraft_path) => prepare_instance(Path::new(&minecraft_path), &launch_settings),
+        Err(_) => Err("Minecraft is not installed!".to_string()),
+    };
+    match instance_path.and_then(|path| download_modpack(app,app.modpack.clone(),path.to_string_lossy().to_string(),&launch_settings)) {
+        Ok(_) => {}
+        Err(err) => {
+            error(&format!("Failed to download modpack: {err}"),app)
+        }
+    }
+}
+
+fn host_section(ui: &mut Ui, app: &mut App){
+    let prev_active = app.hosts.active;
+    egui::ComboBox::from_label("Host")
+        .selected_text(&app.hosts.active().name)
+        .show_ui(ui, |ui| {
+            for index in 0..app.hosts.hosts.len() {
+                let name = app.hosts.hosts[index].name.clone();
+                ui.selectable_value(&mut app.hosts.active, index, name);
+            }
+        });
+    let mut switched = app.hosts.active != prev_active;
+    ui.horizontal(|ui| {
+        if ui.small_button("Add Host").clicked() {
+            app.hosts.add(HostProfile::new("New Host", "https://"));
+            switched = true;
+        }
+        if ui.small_button("Remove Host").clicked() {
+            switched = app.hosts.remove_active();
+        }
+    });
+
+    let host = app.hosts.active_mut();
+    ui.label("Name:");
+    let edited = ui.text_edit_singleline(&mut host.name).lost_focus();
+    ui.label("Host URL:");
+    let mut reconnect = ui.add(egui::TextEdit::singleline(&mut host.url).hint_text("https://example.com:7878")).lost_focus();
+    if is_plain_http(&host.url) {
+        ui.label(RichText::new("plain HTTP: mods can be tampered with on the way, use https").color(Color32::RED));
+    }
+    ui.label("Certificate Fingerprint (SHA-256):");
+    reconnect |= ui.add(egui::TextEdit::singleline(&mut host.cert_sha256).hint_text("only for self-signed hosts")).lost_focus();
+    reconnect |= pack_server_login_section(ui, host);
+
+    ui.label("Mirrors:");
+    let mut mirrors_text = host.mirrors.join("\n");
+    let response = ui.add(egui::TextEdit::multiline(&mut mirrors_text).desired_rows(2).hint_text("hosts with the same packs, one per line"));
+    if response.changed() {
+        host.mirrors = mirrors_text.split('\n').map(|line| line.to_string()).collect();
+    }
+    reconnect |= response.lost_focus();
+
+    ui.label("Trusted Signing Keys:");
+    let mut keys_text = host.trusted_keys.join("\n");
+    let response = ui.add(egui::TextEdit::multiline(&mut keys_text).desired_rows(2).hint_text("ed25519 public keys in hex, one per line"));
+    if response.changed() {
+        host.trusted_keys = keys_text.split('\n').map(|line| line.to_string()).collect();
+    }
+    reconnect |= response.lost_focus();
+
+    if switched || reconnect {
+        on_host_changed(app);
+    } else if edited {
+        if let Err(err) = app.hosts.save() {
+            error(&format!("Could not save the hosts: {err}"), app);
+        }
+    }
+}
+
+fn lan_hosts_section(ui: &mut Ui, app: &mut App){
+    ui.horizontal(|ui| {
+        ui.label("LAN Hosts");
+        if ui.small_button("Search").clicked() {
+            app.lan_discovery = Some(ImmediateValuePromise::new(async {
+                Ok(discover_hosts().await)
+            }));
+        }
+    });
+    let hosts = match app.lan_discovery.as_mut().map(|discovery| discovery.poll_state()) {
+        None => return,
+        Some(ImmediateValueState::Success(Ok(hosts))) => hosts.clone(),
+        Some(ImmediateValueState::Success(Err(err))) => {
+            ui.label(RichText::new(format!("Could not search the network: {err}")).weak());
+            return;
+        }
+        Some(ImmediateValueState::Error(err)) => {
+            ui.label(RichText::new(format!("Could not search the network: {0}", **err)).weak());
+            return;
+        }
+        _ => {
+            ui.spinner();
+            return;
+        }
+    };
+    if hosts.is_empty() {
+        ui.label(RichText::new("no pack servers answered").weak());
+        return;
+    }
+    let mut picked = None;
+    for host in &hosts {
+        let selected = app.hosts.active().base_url() == host.url;
+        if ui.selectable_label(selected, format!("{0} ({1})", host.name, host.url)).clicked() {
+            picked = Some(host.clone());
+        }
+    }
+    let host = match picked {
+        Some(host) => host,
+        None => return,
+    };
+    match app.hosts.hosts.iter().position(|profile| profile.base_url() == host.url) {
+        Some(index) => app.hosts.active = index,
+        None => app.hosts.add(HostProfile::new(&host.name, &host.url)),
+    }
+    info(&format!("Using pack server {0} at {1}", host.name, host.url), app);
+    on_host_changed(app);
+}
+
+fn on_host_changed(app: &mut App){
+    app.catalog_fetch = None;
+    app.catalog_fresh = false;
+    if let Err(err) = app.hosts.save() {
+        error(&format!("Could not save the hosts: {err}"), app);
+    }
+    on_modpack_changed(app);
+}
+
+/// Returns true when the login changed, so requests are retried with it.
+fn pack_server_login_section(ui: &mut Ui, host: &mut HostProfile) -> bool{
+    let prev_kind = host.auth_kind;
+    egui::ComboBox::from_label("Pack Server Login")
+        .selected_text(host.auth_kind.as_str())
+        .show_ui(ui, |ui| {
+            for kind in HostAuthKind::vec_all() {
+                ui.selectable_value(&mut host.auth_kind, kind, kind.as_str());
+            }
+        });
+    let mut changed = host.auth_kind != prev_kind;
+    match host.auth_kind {
+        HostAuthKind::None => {}
+        HostAuthKind::Bearer => {
+            ui.label("Token:");
+            changed |= ui.add(egui::TextEdit::singleline(&mut host.auth_secret).password(true)).lost_focus();
+        }
+        HostAuthKind::Basic => {
+            ui.label("Username:");
+            changed |= ui.text_edit_singleline(&mut host.auth_username).lost_focus();
+            ui.label("Password:");
+            changed |= ui.add(egui::TextEdit::singleline(&mut host.auth_secret).password(true)).lost_focus();
+        }
+    }
+    changed
+}
+
+/// Packs grouped by the host that lists them, imported packs come last.
+fn modpack_selector(ui: &mut Ui, app: &mut App){
+    let mut picked = None;
+    egui::ComboBox::from_label("Modpack")
+        .selected_text(format!("{0:?}",app.modpack.get_name()))
+        .show_ui(ui, |ui| {
+            for (index, host) in app.hosts.hosts.iter().enumerate() {
+                ui.label(RichText::new(&host.name).strong());
+                for entry in &host.catalog {
+                    let pack = Modpack::from_catalog(entry);
+                    let selected = index == app.hosts.active && pack == app.modpack;
+                    if ui.selectable_label(selected, pack.get_name()).clicked() {
+                        picked = Some((Some(index), pack));
+                    }
+                }
+            }
+            let local_packs = list_local_packs();
+            if !local_packs.is_empty() {
+                ui.label(RichText::new("Local").strong());
+            }
+            for id in local_packs {
+                let pack = Modpack::Local(id);
+                if ui.selectable_label(pack == app.modpack, pack.get_name()).clicked() {
+                    picked = Some((None, pack));
+                }
+            }
+        });
+    let (host, pack) = match picked {
+        Some(picked) => picked,
+        None => return,
+    };
+    let switched = host.is_some_and(|host| host != app.hosts.active);
+    if let Some(host) = host {
+        app.hosts.active = host;
+    }
+    let pack_changed = pack != app.modpack;
+    app.modpack = pack;
+    if switched {
+        on_host_changed(app);
+    } else if pack_changed {
+        on_modpack_changed(app);
+    }
+}
+
+fn poll_catalog_fetch(app: &mut App){
+    if app.catalog_fetch.is_none() {
+        if !app.catalog_fresh {
+            app.catalog_fresh = true;
+            app.catalog_fetch = Some(start_catalog_fetch(&LaunchSettings::from_app(app)));
+        }
+        return;
+    }
+    let result = match app.catalog_fetch.as_mut().unwrap().poll_state() {
+        ImmediateValueState::Success(result) => result.clone(),
+        ImmediateValueState::Error(err) => Err(format!("{0}", **err)),
+        _ => return,
+    };
+    app.catalog_fetch = None;
+    let name = app.hosts.active().name.clone();
+    match result {
+        Ok(catalog) => {
+            if app.hosts.active().catalog != catalog {
+                info(&format!("{name} lists {0} packs", catalog.len()), app);
+                app.hosts.active_mut().catalog = catalog;
+                if let Err(err) = app.hosts.save() {
+                    error(&format!("Could not save the hosts: {err}"), app);
+                }
+            }
+        }
+        Err(err) => warn(&format!("Could not load the pack list of {name}: {err}"), app),
+    }
+}
+
+fn on_modpack_changed(app: &mut App){
+    app.remote_manifest = None;
+    app.pack_history = None;
+    app.update_check = None;
+    app.update_status = None;
+    app.mod_selection = ModSelection::load(&app.modpack.state_id(app.hosts.active()));
+    app.mod_list = None;
+
+    // imported packs carry the game versions they were built for
+    if let Modpack::Local(id) = &app.modpack {
+        if let Ok(manifest) = load_local_manifest(id) {
+            if let Some(version) = manifest.minecraft_version {
+                app.minecraft_version = version;
+            }
+            if let Some(version) = manifest.forge_version {
+                app.forge_version = version;
+            }
+        }
+    }
+}
+
+fn pack_files_section(ui: &mut Ui, app: &mut App){
+    // dropping a pack file on the window fills in its path
+    let dropped = ui.ctx().input(|input| input.raw.dropped_files.iter().find_map(|file| file.path.clone()));
+    if let Some(path) = dropped {
+        app.pack_file_path = path.display().to_string();
+    }
+
+    ui.label("Pack File");
+    ui.add(egui::TextEdit::singleline(&mut app.pack_file_path).hint_text("drop a .mrpack or CurseForge .zip here"));
+    ui.label("CurseForge API Key");
+    ui.add(egui::TextEdit::singleline(&mut app.curseforge_api_key).password(true));
+
+    if app.import_task.is_some() {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("importing...");
+        });
+        return;
+    }
+
+    if ui.button("Import Pack").clicked() {
+        let path = PathBuf::from(app.pack_file_path.trim());
+        info(&format!("Importing {0}", path.display()), app);
+        let is_curseforge = path.extension().map(|ext| ext.eq_ignore_ascii_case("zip")).unwrap_or(false);
+        app.import_task = Some(match is_curseforge {
+            true => {
+                let resolver = Box::new(CurseForgeApiResolver{ api_key: app.curseforge_api_key.clone() });
+                ImmediateValuePromise::new(async move {
+                    Ok(import_curseforge(path, resolver).await)
+                })
+            }
+            false => ImmediateValuePromise::new(async move {
+                Ok(import_mrpack(path).await)
+            }),
+        });
+    }
+
+    if ui.button("Export .mrpack").on_hover_text("saved to the desktop as <pack>.mrpack").clicked() {
+        let launch_settings = LaunchSettings::from_app(app);
+        // never the import path above, that is the user's source archive
+        let out_path = UserDirs::new()
+            .and_then(|dirs| dirs.desktop_dir().map(|dir| dir.to_path_buf()))
+            .unwrap_or_default()
+            .join(format!("{0}.mrpack", app.modpack.get_server_identifier()));
+        match instance_dir(&launch_settings.state_id).and_then(|instance_path| export_mrpack(&launch_settings, &instance_path, &out_path)) {
+            Ok(count) => info(&format!("Exported {count} files to {0}", out_path.display()), app),
+            Err(err) => error(&format!("Failed to export {0}: {err}", app.modpack.get_name()), app),
+        }
+    }
+}
+
+fn poll_import_task(app: &mut App){
+    let report = match app.import_task.as_mut().map(|task| task.poll_state()) {
+        Some(ImmediateValueState::Success(_)) | Some(ImmediateValueState::Error(_)) => {
+            match app.import_task.take().unwrap().poll_state_mut().take_result() {
+                Some(Ok(report)) => report,
+                _ => Err("import task failed".to_string()),
+            }
+        }
+        _ => return,
+    };
+    match report {
+        Ok(report) => {
+            for note in &report.notes {
+                warn(note, app);
+            }
+            info(&format!("Imported {0} with {1} files as a local pack", report.name, report.file_count), app);
+            app.modpack = Modpack::Local(report.pack_id);
+            on_modpack_changed(app);
+            info(&format!("Using minecraft {0} with forge {1} for this pack", app.minecraft_version, app.forge_version), app);
+        }
+        Err(err) => error(&format!("Import failed: {err}"), app),
+    }
+}
+
+fn optional_mods_section(ui: &mut Ui, app: &mut App){
+    ui.horizontal(|ui| {
+        ui.label("Optional Mods");
+        if ui.small_button("Refresh").clicked() {
+            app.remote_manifest = None;
+        }
+    });
+    if app.remote_manifest.is_none() {
+        app.remote_manifest = Some(start_manifest_fetch(&LaunchSettings::from_app(app)));
+    }
+
+    let manifest = match app.remote_manifest.as_mut().unwrap().poll_state() {
+        ImmediateValueState::Success(Ok(manifest)) => manifest,
+        ImmediateValueState::Success(Err(err)) => {
+            ui.label(RichText::new(format!("Could not load the pack: {err}")).weak());
+            return;
+        }
+        ImmediateValueState::Error(err) => {
+            ui.label(RichText::new(format!("Could not load the pack: {0}", **err)).weak());
+            return;
+        }
+        _ => {
+            ui.spinner();
+            return;
+        }
+    };
+
+    let optional_files = manifest.optional_files();
+    if optional_files.is_empty() {
+        ui.label(RichText::new("this pack has no optional mods").weak());
+        return;
+    }
+    let mut changed = false;
+    for file in optional_files {
+        let mut enabled = app.mod_selection.is_enabled(file);
+        let response = ui.checkbox(&mut enabled, &file.name);
+        let response = match file.description.is_empty() {
+            true => response,
+            false => response.on_hover_text(&file.description),
+        };
+        if response.changed() {
+            app.mod_selection.set_enabled(file, enabled);
+            changed = true;
+        }
+    }
+    if changed {
+        app.update_check = None;
+        if let Err(err) = app.mod_selection.save(&app.modpack.state_id(app.hosts.active())) {
+            error(&format!("Could not save optional mod selection: {err}"), app);
+        }
+    }
+}
+
+fn poll_update_check(app: &mut App){
+    // a running sync changes what is installed, check again once it is done
+    if matches!(app.download_callback.as_mut().map(|callback| callback.poll_state()), Some(DataState::Updating(_))) {
+        app.update_check = None;
+        return;
+    }
+    let due = app.update_checked_at.elapsed() >= UPDATE_CHECK_INTERVAL;
+    let status = match app.update_check.as_mut().map(|check| check.poll_state()) {
+        None => None,
+        Some(ImmediateValueState::Success(Ok(status))) => Some(status.clone()),
+        Some(ImmediateValueState::Updating) => return,
+        Some(_) if !due => return,
+        Some(_) => {
+            app.update_check = None;
+            return;
+        }
+    };
+    if app.update_check.is_none() || due {
+        app.update_check = Some(start_update_check(&LaunchSettings::from_app(app)));
+        app.update_checked_at = Instant::now();
+        if let Some(ctx) = &app.update_callback_ctx {
+            ctx.request_repaint_after(UPDATE_CHECK_INTERVAL);
+        }
+    }
+    if let Some(status) = status {
+        if app.update_status.as_ref() != Some(&status) {
+            if !status.is_up_to_date() {
+                info(&format!("Update available for {0}: {1}", app.modpack.get_name(), status.describe()), app);
+            }
+            app.update_status = Some(status);
+        }
+    }
+}
+
+fn update_status_section(ui: &mut Ui, app: &mut App){
+    ui.horizontal(|ui| {
+        ui.label("Updates:");
+        match app.update_check.as_mut().map(|check| check.poll_state()) {
+            Some(ImmediateValueState::Success(Ok(status))) => {
+                ui.label(status.describe());
+            }
+            Some(ImmediateValueState::Success(Err(err))) => {
+                ui.label(RichText::new(format!("could not check: {err}")).weak());
+            }
+            Some(ImmediateValueState::Error(err)) => {
+                ui.label(RichText::new(format!("could not check: {0}", **err)).weak());
+            }
+            _ => {
+                ui.spinner();
+            }
+        }
+        if ui.small_button("Check").clicked() {
+            app.update_check = None;
+        }
+    });
+}
+
+fn history_section(ui: &mut Ui, app: &mut App){
+    ui.label("Update History");
+    // reload once the running sync has recorded its revision
+    let syncing = matches!(app.download_callback.as_mut().map(|callback| callback.poll_state()), Some(DataState::Updating(_)));
+    if syncing || app.pack_history.is_none() {
+        app.pack_history = Some(PackHistory::load(&app.modpack.state_id(app.hosts.active())));
+    }
+    let history = app.pack_history.clone().unwrap_or_default();
+    if history.revisions.is_empty() {
+        ui.label(RichText::new("this pack has not been installed yet").weak());
+        return;
+    }
+
+    let current = history.current_revision().map(|revision| revision.manifest_hash.clone());
+    for revision in history.revisions.iter().rev() {
+        let mut text = format!("{0}  {1}  ({2} files)", revision.installed_at.format("%Y-%m-%d %H:%M"), revision.short_hash(), revision.files.len());
+        if current.as_ref() == Some(&revision.manifest_hash) {
+            text.push_str("  current");
+        }
+        ui.label(RichText::new(text).monospace());
+    }
+
+    let pack_id = app.modpack.state_id(app.hosts.active());
+    let pack_name = app.modpack.get_name();
+    let mut pinned = None;
+    ui.horizontal(|ui| {
+        if let Some(previous) = history.previous_revision() {
+            let uncached = previous.uncached_files().len();
+            let response = ui.add_enabled(!syncing && uncached == 0, egui::Button::new("Roll Back"))
+                .on_disabled_hover_text(format!("{uncached} files of revision {0} are no longer cached", previous.short_hash()));
+            if response.clicked() {
+                pinned = Some(Some(previous.manifest_hash.clone()));
+            }
+        }
+        if history.pinned.is_some() && ui.add_enabled(!syncing, egui::Button::new("Follow Updates")).clicked() {
+            pinned = Some(None);
+        }
+    });
+    let pinned = match pinned {
+        Some(pinned) => pinned,
+        None => return,
+    };
+
+    let mut history = history;
+    history.pinned = pinned;
+    if let Err(err) = history.save(&pack_id) {
+        error(&format!("Could not save the update history: {err}"), app);
+        return;
+    }
+    match history.pinned_revision() {
+        Some(revision) => info(&format!("Rolling {pack_name} back to revision {0} from {1}", revision.short_hash(), revision.installed_at.format("%Y-%m-%d %H:%M")), app),
+        None => info(&format!("{pack_name} follows server updates again"), app),
+    }
+    app.pack_history = Some(history);
+    download_to_instance(app);
+}
+
 fn bottom_panel(ui: &mut Ui, app: &mut App){
     ui.vertical_centered(|ui| {
         match &mut app.download_callback {
@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::storage::{pack_dir, read_json, write_json};
+use crate::hashing::is_sha256;
+
+const INSTALLED_MANIFEST_FILE:&str = "manifest.json";
+/// Game subdirectories a pack is allowed to write into.
+const TARGET_DIRS:[&str; 6] = ["mods", "config", "resourcepacks", "shaderpacks", "kubejs", "defaultconfigs"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side{
+    Client,
+    Server,
+    #[default]
+    Both,
+}
+
+impl Side{
+    /// Whether a file marked with this side belongs in an install for `target`.
+    pub fn includes(&self, target: Side) -> bool{
+        *self == Side::Both || *self == target
+    }
+}
+
+/// What to do with a file that already exists in the game folder.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdatePolicy{
+    /// Always replace the local file with the pack's copy.
+    #[default]
+    Overwrite,
+    /// Replace the local file only if the user has not changed it since the last sync,
+    /// otherwise keep it and save the pack's copy next to it. For configs users tweak.
+    Preserve,
+}
+
+fn default_target() -> String{
+    "mods".to_string()
+}
+
+/// Turns a path from the manifest into a relative path that cannot leave its target folder.
+fn safe_relative_path(path: &str) -> Result<PathBuf, String>{
+    let mut relative = PathBuf::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(format!("'{path}' points outside of the game folder")),
+            part if part.contains(':') => return Err(format!("'{path}' is not a relative path")),
+            part => relative.push(part),
+        }
+    }
+    if relative.as_os_str().is_empty() || path.starts_with(['/', '\\']) {
+        return Err(format!("'{path}' is not a relative path"));
+    }
+    Ok(relative)
+}
+
+/// A file served by the pack host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackFile{
+    pub name:String,
+    /// Optional files (minimaps, shaders, ...) are only synced when the user enables them.
+    #[serde(default)]
+    pub optional:bool,
+    #[serde(default = "default_true")]
+    pub enabled_by_default:bool,
+    #[serde(default)]
+    pub description:String,
+    /// Where the file is needed, clients skip server-only files.
+    #[serde(default)]
+    pub side:Side,
+    /// Game subdirectory the file is installed into, `name` may contain further folders.
+    #[serde(default = "default_target")]
+    pub target:String,
+    #[serde(default)]
+    pub policy:UpdatePolicy,
+    /// Lets clients reuse a cached copy instead of downloading the file again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256:Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size:Option<u64>,
+}
+
+fn default_true() -> bool{
+    true
+}
+
+impl PackFile{
+    pub fn new(name: &str) -> PackFile{
+        PackFile{
+            name: name.to_string(),
+            optional: false,
+            enabled_by_default: true,
+            description: "".to_string(),
+            side: Side::Both,
+            target: default_target(),
+            policy: UpdatePolicy::Overwrite,
+            sha256: None,
+            size: None,
+        }
+    }
+
+    /// Splits a path like `config/jei/jei-client.ini` into a pack file targeting `config`.
+    /// Returns `None` for paths outside the folders a pack may write to.
+    pub fn from_install_path(path: &str) -> Option<PackFile>{
+        let (target, name) = path.trim_start_matches('/').split_once('/')?;
+        let mut file = PackFile::new(name);
+        file.target = target.to_string();
+        // configs are what users tweak, keep their edits on updates
+        if target == "config" || target == "defaultconfigs" {
+            file.policy = UpdatePolicy::Preserve;
+        }
+        file.install_path().ok()?;
+        Some(file)
+    }
+
+    /// Path of the file relative to the game folder, e.g. `config/jei/jei-client.ini`.
+    /// Fails for unknown targets and names that try to escape the target folder.
+    pub fn install_path(&self) -> Result<PathBuf, String>{
+        if !TARGET_DIRS.contains(&self.target.as_str()) {
+            return Err(format!("{0} has an unsupported target folder '{1}'", self.name, self.target));
+        }
+        Ok(Path::new(&self.target).join(safe_relative_path(&self.name)?))
+    }
+
+    /// Path of the file on the pack host. Mods are served at the pack root like before,
+    /// everything else under its target folder.
+    pub fn remote_path(&self) -> String{
+        match self.target.as_str() {
+            "mods" => self.name.clone(),
+            target => format!("{target}/{0}", self.name),
+        }
+    }
+}
+
+/// Two mods the pack maintainers know break each other when loaded together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModConflict{
+    pub mods:(String, String),
+    #[serde(default)]
+    pub reason:String,
+}
+
+/// Describes a pack, served as `manifest.json` next to the pack files.
+/// Hosts that only serve the older `metadata` file list are still supported.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PackManifest{
+    #[serde(default)]
+    pub name:String,
+    /// Game versions the pack was built for, set by imported packs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minecraft_version:Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge_version:Option<String>,
+    #[serde(default)]
+    pub files:Vec<PackFile>,
+    #[serde(default)]
+    pub conflicts:Vec<ModConflict>,
+    /// Other base urls serving this pack, tried in order when the host fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors:Vec<String>,
+}
+
+impl PackManifest{
+    pub fn parse(text: &str) -> Result<PackManifest, String>{
+        let manifest: PackManifest = match serde_json::from_str(text) {
+            Ok(manifest) => manifest,
+            Err(err) => return Err(format!("malformed pack manifest: {err}")),
+        };
+        for file in &manifest.files {
+            match &file.sha256 {
+                Some(sha256) if !is_sha256(sha256) => return Err(format!("{0} has a malformed sha256 '{sha256}'", file.name)),
+                _ => {}
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Builds a manifest from the newline separated file list served at `metadata`.
+    pub fn from_file_list(text: &str) -> PackManifest{
+        PackManifest{
+            files: text.lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(PackFile::new)
+                .collect(),
+            ..PackManifest::default()
+        }
+    }
+
+    /// The manifest of the last successful sync of a pack.
+    pub fn load_installed(pack_id: &str) -> PackManifest{
+        match pack_dir(pack_id) {
+            Ok(dir) => read_json(&dir.join(INSTALLED_MANIFEST_FILE)).unwrap_or_default(),
+            Err(_) => PackManifest::default(),
+        }
+    }
+
+    /// The files that make up an install for one side, so the same pack can produce
+    /// both the client download and the server file set.
+    pub fn files_for_side(&self, side: Side) -> Vec<&PackFile>{
+        self.files.iter().filter(|file| file.side.includes(side)).collect()
+    }
+
+    pub fn optional_files(&self) -> Vec<&PackFile>{
+        self.files_for_side(Side::Client).into_iter().filter(|file| file.optional).collect()
+    }
+
+    /// Writes the server side file list in the same format as the `metadata` endpoint,
+    /// files outside of `mods` keep their target folder.
+    pub fn server_file_list(&self) -> String{
+        self.files_for_side(Side::Server)
+            .iter()
+            .filter(|file| file.install_path().is_ok())
+            .map(|file| format!("{0}\n", file.remote_path()))
+            .collect()
+    }
+
+    pub fn save_installed(&self, pack_id: &str) -> Result<(), String>{
+        write_json(&pack_dir(pack_id)?.join(INSTALLED_MANIFEST_FILE), self)
+    }
+}
+
+/// Reads a pack manifest and writes the server side file list next to it, used by
+/// `QModManager --server-files <manifest.json> <out>` from the pack host tooling.
+pub fn export_server_file_list(manifest_path: &Path, out_path: &Path) -> Result<usize, String>{
+    let manifest: PackManifest = read_json(manifest_path)?;
+    let list = manifest.server_file_list();
+    match fs::write(out_path, &list) {
+        Ok(_) => Ok(list.lines().count()),
+        Err(err) => Err(format!("{0}: {err}", out_path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::{safe_relative_path, PackFile, PackManifest, Side};
+
+    #[test]
+    fn relative_paths_are_kept() {
+        assert_eq!(safe_relative_path("jei.jar").unwrap(), PathBuf::from("jei.jar"));
+        assert_eq!(safe_relative_path("jei/./client.ini").unwrap(), PathBuf::from("jei").join("client.ini"));
+        assert_eq!(safe_relative_path("jei\\client.ini").unwrap(), PathBuf::from("jei").join("client.ini"));
+    }
+
+    #[test]
+    fn escaping_paths_are_rejected() {
+        for path in ["..", "../options.txt", "jei/../../options.txt", "jei\\..\\..\\x"] {
+            assert!(safe_relative_path(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn absolute_paths_are_rejected() {
+        for path in ["/etc/passwd", "\\server\\share", "C:\\Windows", "C:foo", "jei/D:x"] {
+            assert!(safe_relative_path(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn empty_names_are_rejected() {
+        for path in ["", ".", "/", "./"] {
+            assert!(safe_relative_path(path).is_err(), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn unknown_targets_are_rejected() {
+        let mut file = PackFile::new("evil.dll");
+        file.target = "..".to_string();
+        assert!(file.install_path().is_err());
+        assert!(PackFile::from_install_path("saves/world/level.dat").is_none());
+    }
+
+    #[test]
+    fn server_file_list_keeps_target_folders() {
+        let mut client_only = PackFile::new("minimap.jar");
+        client_only.side = Side::Client;
+        let mut manifest = PackManifest::from_file_list("jei.jar\n");
+        manifest.files.push(client_only);
+        manifest.files.push(PackFile::from_install_path("config/jei/jei-server.toml").unwrap());
+        assert_eq!(manifest.server_file_list(), "jei.jar\nconfig/jei/jei-server.toml\n");
+    }
+
+    #[test]
+    fn malformed_hashes_are_rejected() {
+        let hash = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let manifest = |sha256: &str| format!(r#"{{"files": [{{"name": "jei.jar", "sha256": "{sha256}"}}]}}"#);
+        assert!(PackManifest::parse(&manifest(hash)).is_ok());
+        assert!(PackManifest::parse(r#"{"files": [{"name": "jei.jar"}]}"#).is_ok());
+        for sha256 in ["../../hosts.json", &hash.to_uppercase(), &hash[1..], &format!("{hash}0"), ""] {
+            assert!(PackManifest::parse(&manifest(sha256)).is_err(), "{sha256}");
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use zip::ZipArchive;
+use crate::hashing::sha256_file;
+use crate::installed::InstalledPack;
+use crate::manifest::ModConflict;
+use crate::versions::VersionRange;
+
+const MODS_TOML_PATH:&str = "META-INF/mods.toml";
+const MANIFEST_PATH:&str = "META-INF/MANIFEST.MF";
+const JAR_VERSION_PLACEHOLDER:&str = "${file.jarVersion}";
+const QUARANTINE_FOLDER:&str = "mods-quarantine";
+
+#[derive(Debug, Deserialize)]
+struct ModsToml{
+    #[serde(default)]
+    mods:Vec<ModsTomlEntry>,
+    #[serde(default)]
+    dependencies:HashMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlEntry{
+    mod_id:String,
+    version:Option<String>,
+    display_name:Option<String>,
+    description:Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlDependency{
+    mod_id:String,
+    /// forge up to 1.20.1 uses `mandatory`, newer loaders use `type`
+    mandatory:Option<bool>,
+    #[serde(rename = "type")]
+    kind:Option<String>,
+    version_range:Option<String>,
+    side:Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyKind{
+    Required,
+    Optional,
+    Incompatible,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModDependency{
+    pub mod_id:String,
+    pub kind:DependencyKind,
+    pub version_range:String,
+    /// Dependencies that only apply on the server are skipped when checking the client.
+    pub server_only:bool,
+}
+
+impl ModDependency{
+    fn from_toml(dependency: ModsTomlDependency) -> ModDependency{
+        let kind = match dependency.kind.as_deref().map(|kind| kind.to_lowercase()) {
+            Some(kind) if kind == "required" => DependencyKind::Required,
+            Some(kind) if kind == "incompatible" => DependencyKind::Incompatible,
+            Some(_) => DependencyKind::Optional,
+            None => match dependency.mandatory {
+                Some(true) => DependencyKind::Required,
+                _ => DependencyKind::Optional,
+            }
+        };
+        ModDependency{
+            mod_id: dependency.mod_id,
+            kind,
+            version_range: dependency.version_range.unwrap_or_default(),
+            server_only: dependency.side.map(|side| side.eq_ignore_ascii_case("SERVER")).unwrap_or(false),
+        }
+    }
+}
+
+/// A mod declared in a jar's `META-INF/mods.toml`.
+#[derive(Debug, Clone)]
+pub struct ModInfo{
+    pub mod_id:String,
+    pub version:String,
+    pub display_name:String,
+    pub description:String,
+    pub dependencies:Vec<ModDependency>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModJar{
+    pub file_name:String,
+    pub path:PathBuf,
+    pub mods:Vec<ModInfo>,
+    pub from_pack:bool,
+    /// Set when the jar could not be read, the jar is still listed so nothing goes missing.
+    pub error:Option<String>,
+}
+
+fn read_zip_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<String>{
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Forge lets mods.toml take its version from the jar manifest.
+fn manifest_version(archive: &mut ZipArchive<File>) -> Option<String>{
+    let manifest = read_zip_entry(archive, MANIFEST_PATH)?;
+    manifest.lines()
+        .find_map(|line| line.strip_prefix("Implementation-Version:"))
+        .map(|version| version.trim().to_string())
+}
+
+pub fn read_mod_jar(path: &Path) -> Result<Vec<ModInfo>, String>{
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(err.to_string()),
+    };
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(err) => return Err(err.to_string()),
+    };
+    let text = match read_zip_entry(&mut archive, MODS_TOML_PATH) {
+        Some(text) => text,
+        None => return Err(format!("no {MODS_TOML_PATH} found")),
+    };
+    let mods_toml: ModsToml = match toml::from_str(&text) {
+        Ok(mods_toml) => mods_toml,
+        Err(err) => return Err(format!("malformed {MODS_TOML_PATH}: {0}", err.message())),
+    };
+
+    let jar_version = manifest_version(&mut archive);
+    let mut dependencies = mods_toml.dependencies;
+    Ok(mods_toml.mods.into_iter().map(|entry| {
+        let version = match entry.version {
+            Some(version) if version == JAR_VERSION_PLACEHOLDER => jar_version.clone().unwrap_or(version),
+            Some(version) => version,
+            None => jar_version.clone().unwrap_or_default(),
+        };
+        let mod_dependencies = dependencies.remove(&entry.mod_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(ModDependency::from_toml)
+            .collect();
+        ModInfo{
+            display_name: entry.display_name.unwrap_or(entry.mod_id.clone()),
+            mod_id: entry.mod_id,
+            version,
+            description: entry.description.unwrap_or_default().trim().to_string(),
+            dependencies: mod_dependencies,
+        }
+    }).collect())
+}
+
+/// Lists every jar in the mods folder, marking the ones the installed pack put there.
+pub fn scan_mods_folder(mods_path: &Path, installed: &InstalledPack) -> Result<Vec<ModJar>, String>{
+    let entries = match fs::read_dir(mods_path) {
+        Ok(entries) => entries,
+        Err(err) => return Err(format!("{0}: {err}", mods_path.display())),
+    };
+    let mut jars = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map(|ext| ext != "jar").unwrap_or(true) {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let (mods, error) = match read_mod_jar(&path) {
+            Ok(mods) => (mods, None),
+            Err(err) => (vec![], Some(err)),
+        };
+        jars.push(ModJar{
+            from_pack: installed.contains_mod(&file_name),
+            file_name,
+            path,
+            mods,
+            error,
+        });
+    }
+    jars.sort_by_key(|jar| jar.file_name.to_lowercase());
+    Ok(jars)
+}
+
+/// Checks every dependency declared by the given jars against the installed mods and the
+/// configured minecraft and forge versions, returning one readable line per problem.
+pub fn check_dependencies(jars: &[ModJar], minecraft_version: &str, forge_version: &str) -> Vec<String>{
+    let mut installed: HashMap<&str, &str> = HashMap::new();
+    installed.insert("minecraft", minecraft_version);
+    installed.insert("forge", forge_version);
+    for jar in jars {
+        for info in &jar.mods {
+            installed.insert(&info.mod_id, &info.version);
+        }
+    }
+
+    let mut problems = vec![];
+    for jar in jars {
+        for info in &jar.mods {
+            for dependency in &info.dependencies {
+                if dependency.server_only || dependency.kind == DependencyKind::Optional && !installed.contains_key(dependency.mod_id.as_str()) {
+                    continue;
+                }
+                let range = match VersionRange::parse(&dependency.version_range) {
+                    Ok(range) => range,
+                    Err(err) => {
+                        problems.push(format!("{0} ({1}) declares an unreadable range for {2}: {err}", info.display_name, jar.file_name, dependency.mod_id));
+                        continue;
+                    }
+                };
+                let found = installed.get(dependency.mod_id.as_str());
+                match (dependency.kind, found) {
+                    (DependencyKind::Required, None) => {
+                        problems.push(format!("{0} ({1}) requires {2} {3}, which is not installed", info.display_name, jar.file_name, dependency.mod_id, dependency.version_range));
+                    }
+                    (DependencyKind::Required, Some(version)) | (DependencyKind::Optional, Some(version)) if !range.contains(version) => {
+                        problems.push(format!("{0} ({1}) requires {2} {3}, but {4} is installed", info.display_name, jar.file_name, dependency.mod_id, dependency.version_range, version));
+                    }
+                    (DependencyKind::Incompatible, Some(version)) if range.contains(version) => {
+                        problems.push(format!("{0} ({1}) is incompatible with {2} {3}", info.display_name, jar.file_name, dependency.mod_id, version));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// A problem with the jars in the mods folder that does not stop the launch by itself.
+#[derive(Debug, Clone)]
+pub struct ModIssue{
+    pub message:String,
+    /// Local extras that can be moved out of the way to resolve the issue.
+    pub extras:Vec<PathBuf>,
+}
+
+/// Keeps the pack's copy of a mod and treats the other jars as extras. If the pack
+/// provides none of them the first jar is kept.
+fn pick_extras(group: &[&ModJar]) -> Vec<PathBuf>{
+    let keep = group.iter().position(|jar| jar.from_pack).unwrap_or(0);
+    group.iter()
+        .enumerate()
+        .filter(|(i, jar)| *i != keep && !jar.from_pack)
+        .map(|(_, jar)| jar.path.clone())
+        .collect()
+}
+
+fn join_file_names(group: &[&ModJar]) -> String{
+    group.iter().map(|jar| jar.file_name.as_str()).collect::<Vec<&str>>().join(", ")
+}
+
+/// Finds jars that declare the same mod id, jars that are byte-identical under different
+/// names, and mods the pack manifest lists as conflicting.
+pub fn find_mod_issues(jars: &[ModJar], conflicts: &[ModConflict]) -> Vec<ModIssue>{
+    let mut issues = vec![];
+
+    let mut by_mod_id: HashMap<&str, Vec<&ModJar>> = HashMap::new();
+    for jar in jars {
+        for info in &jar.mods {
+            let group = by_mod_id.entry(&info.mod_id).or_default();
+            if !group.iter().any(|other| other.path == jar.path) {
+                group.push(jar);
+            }
+        }
+    }
+    let mut mod_ids = by_mod_id.keys().copied().collect::<Vec<&str>>();
+    mod_ids.sort();
+    for mod_id in mod_ids {
+        let group = &by_mod_id[mod_id];
+        if group.len() > 1 {
+            issues.push(ModIssue{
+                message: format!("{mod_id} is declared by {0} jars: {1}", group.len(), join_file_names(group)),
+                extras: pick_extras(group),
+            });
+        }
+    }
+
+    let mut by_hash: HashMap<String, Vec<&ModJar>> = HashMap::new();
+    for jar in jars {
+        if let Ok(hash) = sha256_file(&jar.path) {
+            by_hash.entry(hash).or_default().push(jar);
+        }
+    }
+    for group in by_hash.values() {
+        if group.len() > 1 {
+            issues.push(ModIssue{
+                message: format!("identical jars under different names: {0}", join_file_names(group)),
+                extras: pick_extras(group),
+            });
+        }
+    }
+
+    for conflict in conflicts {
+        let (a, b) = &conflict.mods;
+        let (group_a, group_b) = match (by_mod_id.get(a.as_str()), by_mod_id.get(b.as_str())) {
+            (Some(group_a), Some(group_b)) => (group_a, group_b),
+            _ => continue,
+        };
+        // only local jars are moved, if both sides come from the pack the maintainers have to fix it
+        let a_local = group_a.iter().all(|jar| !jar.from_pack);
+        let b_local = group_b.iter().all(|jar| !jar.from_pack);
+        let extras = match (a_local, b_local) {
+            (true, false) => group_a.iter().map(|jar| jar.path.clone()).collect(),
+            (false, true) => group_b.iter().map(|jar| jar.path.clone()).collect(),
+            _ => vec![],
+        };
+        let mut message = format!("{a} ({0}) conflicts with {b} ({1})", join_file_names(group_a), join_file_names(group_b));
+        if !conflict.reason.is_empty() {
+            message.push_str(&format!(": {0}", conflict.reason));
+        }
+        issues.push(ModIssue{ message, extras });
+    }
+    issues
+}
+
+/// Moves jars out of the mods folder into `mods-quarantine` next to it, returning how many moved.
+pub fn quarantine_jars(mods_path: &Path, jars: &[PathBuf]) -> Result<usize, String>{
+    let quarantine_path = match mods_path.parent() {
+        Some(parent) => parent.join(QUARANTINE_FOLDER),
+        None => return Err(format!("{0} has no parent folder", mods_path.display())),
+    };
+    if let Err(err) = fs::create_dir_all(&quarantine_path) {
+        return Err(err.to_string());
+    }
+    let mut moved = 0;
+    for jar in jars {
+        let file_name = match jar.file_name() {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        if !jar.exists() {
+            continue;
+        }
+        if let Err(err) = fs::rename(jar, quarantine_path.join(file_name)) {
+            return Err(format!("{0}: {err}", jar.display()));
+        }
+        moved += 1;
+    }
+    Ok(moved)
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::hashing::{sha1_bytes, sha512_bytes};
+use crate::installed::InstalledPack;
+use crate::local_pack::{local_pack_id, write_local_pack, ImportReport};
+use crate::manifest::{PackFile, PackManifest, Side};
+use crate::launch::LaunchSettings;
+use reqwest::Client;
+use crate::http::{is_web_url, HttpSettings};
+use crate::pack::{fetch_bytes, pack_url};
+use crate::Modpack;
+
+const INDEX_FILE:&str = "modrinth.index.json";
+const OVERRIDES:&str = "overrides/";
+const CLIENT_OVERRIDES:&str = "client-overrides/";
+const SERVER_OVERRIDES:&str = "server-overrides/";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MrpackIndex{
+    format_version:u32,
+    game:String,
+    version_id:String,
+    name:String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary:Option<String>,
+    files:Vec<MrpackFile>,
+    dependencies:HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MrpackFile{
+    path:String,
+    hashes:HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env:Option<MrpackEnv>,
+    downloads:Vec<String>,
+    file_size:u64,
+}
+
+/// `required`, `optional` or `unsupported` for each side.
+#[derive(Debug, Serialize, Deserialize)]
+struct MrpackEnv{
+    client:String,
+    server:String,
+}
+
+fn side_from_env(env: &Option<MrpackEnv>) -> (Side, bool){
+    let env = match env {
+        Some(env) => env,
+        None => return (Side::Both, false),
+    };
+    let side = match (env.client.as_str(), env.server.as_str()) {
+        ("unsupported", _) => Side::Server,
+        (_, "unsupported") => Side::Client,
+        _ => Side::Both,
+    };
+    (side, env.client == "optional")
+}
+
+fn env_for(side: Side, optional: bool) -> Option<MrpackEnv>{
+    if side == Side::Both && !optional {
+        return None;
+    }
+    Some(MrpackEnv{
+        client: match (side, optional) {
+            (Side::Server, _) => "unsupported".to_string(),
+            (_, true) => "optional".to_string(),
+            _ => "required".to_string(),
+        },
+        server: match side {
+            Side::Client => "unsupported".to_string(),
+            _ => "required".to_string(),
+        },
+    })
+}
+
+/// Everything that has to come out of the archive, read up front so no zip handle is held
+/// across the downloads.
+struct MrpackContent{
+    index:MrpackIndex,
+    overrides:Vec<(String, Side, Vec<u8>)>,
+}
+
+fn read_mrpack(archive_path: &Path) -> Result<MrpackContent, String>{
+    let file = match File::open(archive_path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("{0}: {err}", archive_path.display())),
+    };
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let index: MrpackIndex = {
+        let mut entry = match archive.by_name(INDEX_FILE) {
+            Ok(entry) => entry,
+            Err(_) => return Err(format!("{INDEX_FILE} is missing, this is not a modrinth pack")),
+        };
+        let mut text = String::new();
+        if let Err(err) = entry.read_to_string(&mut text) {
+            return Err(err.to_string());
+        }
+        match serde_json::from_str(&text) {
+            Ok(index) => index,
+            Err(err) => return Err(format!("malformed {INDEX_FILE}: {err}")),
+        }
+    };
+    if index.game != "minecraft" {
+        return Err(format!("this pack is for {0}, not minecraft", index.game));
+    }
+
+    let mut overrides = vec![];
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(err) => return Err(err.to_string()),
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let (path, side) = if let Some(path) = name.strip_prefix(CLIENT_OVERRIDES) {
+            (path.to_string(), Side::Client)
+        } else if let Some(path) = name.strip_prefix(SERVER_OVERRIDES) {
+            (path.to_string(), Side::Server)
+        } else if let Some(path) = name.strip_prefix(OVERRIDES) {
+            (path.to_string(), Side::Both)
+        } else {
+            continue;
+        };
+        let mut bytes = vec![];
+        if let Err(err) = entry.read_to_end(&mut bytes) {
+            return Err(err.to_string());
+        }
+        overrides.push((path, side, bytes));
+    }
+    // side specific overrides are applied after the shared ones
+    overrides.sort_by_key(|(_, side, _)| *side != Side::Both);
+    Ok(MrpackContent{ index, overrides })
+}
+
+fn check_hashes(file: &MrpackFile, bytes: &[u8]) -> Result<(), String>{
+    if let Some(sha512) = file.hashes.get("sha512") {
+        match sha512.eq_ignore_ascii_case(&sha512_bytes(bytes)) {
+            true => Ok(()),
+            false => Err("sha512 does not match".to_string()),
+        }
+    } else if let Some(sha1) = file.hashes.get("sha1") {
+        match sha1.eq_ignore_ascii_case(&sha1_bytes(bytes)) {
+            true => Ok(()),
+            false => Err("sha1 does not match".to_string()),
+        }
+    } else {
+        Err(format!("{0} has no sha1 or sha512 hash", file.path))
+    }
+}
+
+/// Downloads a file from the first working url and checks it against the hashes in the index.
+/// The index comes from an untrusted archive, so only http(s) urls are followed.
+async fn resolve_file(client: &Client, file: &MrpackFile) -> Result<Vec<u8>, String>{
+    if !file.hashes.contains_key("sha512") && !file.hashes.contains_key("sha1") {
+        return Err(format!("{0} has no sha1 or sha512 hash", file.path));
+    }
+    let mut last_error = format!("{0} has no download urls", file.path);
+    for url in &file.downloads {
+        if !is_web_url(url) {
+            last_error = format!("{url}: only http(s) downloads are allowed");
+            continue;
+        }
+        let bytes = match fetch_bytes(client, url).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                last_error = format!("{url}: {err}");
+                continue;
+            }
+        };
+        match check_hashes(file, &bytes) {
+            Ok(_) => return Ok(bytes),
+            Err(err) => last_error = format!("{url}: {err}"),
+        }
+    }
+    Err(last_error)
+}
+
+/// Applies the overrides on top of the resolved files, in the order `read_mrpack` returns them.
+fn apply_overrides(files: &mut Vec<(PackFile, Vec<u8>)>, overrides: Vec<(String, Side, Vec<u8>)>, notes: &mut Vec<String>){
+    for (path, side, bytes) in overrides {
+        let mut file = match PackFile::from_install_path(&path) {
+            Some(file) => file,
+            None => {
+                notes.push(format!("skipped override {path}, packs can not install files there"));
+                continue;
+            }
+        };
+        file.side = side;
+        // overrides replace resolved files with the same path
+        files.retain(|(existing, _)| existing.remote_path() != file.remote_path());
+        files.push((file, bytes));
+    }
+}
+
+/// Imports a `.mrpack` into a local pack: resolves every file entry, verifies its hashes and
+/// applies `overrides/`, then `client-overrides/` and `server-overrides/` on top.
+pub async fn import_mrpack(archive_path: PathBuf) -> Result<ImportReport, String>{
+    let content = read_mrpack(&archive_path)?;
+    let client = HttpSettings::default().client()?;
+    let index = content.index;
+    let mut notes = vec![];
+    let mut files: Vec<(PackFile, Vec<u8>)> = vec![];
+
+    for entry in &index.files {
+        let mut file = match PackFile::from_install_path(&entry.path) {
+            Some(file) => file,
+            None => {
+                notes.push(format!("skipped {0}, packs can not install files there", entry.path));
+                continue;
+            }
+        };
+        let (side, optional) = side_from_env(&entry.env);
+        file.side = side;
+        file.optional = optional;
+        let bytes = resolve_file(&client, entry).await?;
+        files.push((file, bytes));
+    }
+
+    apply_overrides(&mut files, content.overrides, &mut notes);
+
+    let manifest = PackManifest{
+        name: index.name.clone(),
+        minecraft_version: index.dependencies.get("minecraft").cloned(),
+        forge_version: index.dependencies.get("forge").cloned(),
+        files: files.iter().map(|(file, _)| file.clone()).collect(),
+        ..PackManifest::default()
+    };
+    for loader in ["neoforge", "fabric-loader", "quilt-loader"] {
+        if index.dependencies.contains_key(loader) {
+            notes.push(format!("this pack uses {loader}, only forge is supported"));
+        }
+    }
+
+    let pack_id = local_pack_id(&index.name);
+    write_local_pack(&pack_id, &manifest, &files)?;
+    Ok(ImportReport{
+        pack_id,
+        name: index.name,
+        file_count: files.len(),
+        notes,
+    })
+}
+
+/// Exports the installed state of a pack as a `.mrpack`. Mods of packs from a host are
+/// listed with their download url, everything else is stored in `overrides/`.
+pub fn export_mrpack(launch_settings: &LaunchSettings, instance_path: &Path, out_path: &Path) -> Result<usize, String>{
+    let pack_id = launch_settings.state_id.as_str();
+    let url = pack_url(launch_settings);
+    let pack_url = match launch_settings.modpack {
+        Modpack::Local(_) => None,
+        _ => Some(url.as_str()),
+    };
+    let manifest = PackManifest::load_installed(pack_id);
+    let installed = InstalledPack::load(pack_id);
+    if installed.files.is_empty() {
+        return Err(format!("{0} has not been synced yet", launch_settings.modpack.get_name()));
+    }
+
+    let mut index_files = vec![];
+    let mut overrides = vec![];
+    let mut file_count = 0;
+    for file in &manifest.files {
+        let install_path = match file.install_path() {
+            Ok(path) => path.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        if installed.installed_hash(&install_path).is_none() {
+            continue;
+        }
+        let bytes = match std::fs::read(instance_path.join(&install_path)) {
+            Ok(bytes) => bytes,
+            Err(err) => return Err(format!("{install_path}: {err}")),
+        };
+        file_count += 1;
+
+        match (pack_url, file.target.as_str()) {
+            (Some(url), "mods") => {
+                index_files.push(MrpackFile{
+                    path: install_path,
+                    hashes: HashMap::from([
+                        ("sha1".to_string(), sha1_bytes(&bytes)),
+                        ("sha512".to_string(), sha512_bytes(&bytes)),
+                    ]),
+                    env: env_for(file.side, file.optional),
+                    downloads: vec![format!("{url}/{0}", file.remote_path())],
+                    file_size: bytes.len() as u64,
+                });
+            }
+            _ => overrides.push((install_path, file.side, bytes)),
+        }
+    }
+
+    let dependencies = HashMap::from([
+        ("minecraft".to_string(), manifest.minecraft_version.clone().unwrap_or(launch_settings.minecraft_version.clone())),
+        ("forge".to_string(), manifest.forge_version.clone().unwrap_or(launch_settings.forge_version.clone())),
+    ]);
+    let index = MrpackIndex{
+        format_version: 1,
+        game: "minecraft".to_string(),
+        version_id: chrono::Local::now().format("%Y.%m.%d").to_string(),
+        name: match manifest.name.is_empty() {
+            true => launch_settings.modpack.get_server_identifier().to_string(),
+            false => manifest.name,
+        },
+        summary: None,
+        files: index_files,
+        dependencies,
+    };
+    write_mrpack(out_path, &index, &overrides)?;
+    Ok(file_count)
+}
+
+fn write_mrpack(out_path: &Path, index: &MrpackIndex, overrides: &[(String, Side, Vec<u8>)]) -> Result<(), String>{
+    let out_file = match File::create(out_path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("{0}: {err}", out_path.display())),
+    };
+    let mut zip = ZipWriter::new(out_file);
+    for (path, side, bytes) in overrides {
+        let folder = match side {
+            Side::Both => OVERRIDES,
+            Side::Client => CLIENT_OVERRIDES,
+            Side::Server => SERVER_OVERRIDES,
+        };
+        if let Err(err) = zip.start_file(format!("{folder}{path}"), SimpleFileOptions::default()) {
+            return Err(err.to_string());
+        }
+        if let Err(err) = zip.write_all(bytes) {
+            return Err(err.to_string());
+        }
+    }
+    let text = match serde_json::to_string_pretty(index) {
+        Ok(text) => text,
+        Err(err) => return Err(err.to_string()),
+    };
+    if let Err(err) = zip.start_file(INDEX_FILE, SimpleFileOptions::default()) {
+        return Err(err.to_string());
+    }
+    if let Err(err) = zip.write_all(text.as_bytes()) {
+        return Err(err.to_string());
+    }
+    match zip.finish() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::hashing::{sha1_bytes, sha256_bytes, sha512_bytes};
+    use crate::hosts::CatalogEntry;
+    use crate::http::HttpSettings;
+    use crate::installed::{InstalledFile, InstalledPack};
+    use crate::instance::instance_dir;
+    use crate::launch::LaunchSettings;
+    use crate::local_pack::{load_local_manifest, local_pack_dir};
+    use crate::manifest::{PackFile, PackManifest, Side};
+    use crate::test_server::serve;
+    use crate::Modpack;
+    use super::{apply_overrides, check_hashes, env_for, export_mrpack, import_mrpack, read_mrpack, resolve_file, side_from_env, write_mrpack, MrpackEnv, MrpackFile, MrpackIndex};
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("qmodmanager-mrpack-{name}-{0}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn env(client: &str, server: &str) -> Option<MrpackEnv>{
+        Some(MrpackEnv{ client: client.to_string(), server: server.to_string() })
+    }
+
+    fn mrpack_file(path: &str, hashes: &[(&str, String)], downloads: &[String]) -> MrpackFile{
+        MrpackFile{
+            path: path.to_string(),
+            hashes: hashes.iter().map(|(kind, hash)| (kind.to_string(), hash.clone())).collect(),
+            env: None,
+            downloads: downloads.to_vec(),
+            file_size: 0,
+        }
+    }
+
+    fn index(files: Vec<MrpackFile>) -> MrpackIndex{
+        MrpackIndex{
+            format_version: 1,
+            game: "minecraft".to_string(),
+            version_id: "1.0.0".to_string(),
+            name: "Test Pack".to_string(),
+            summary: None,
+            files,
+            dependencies: HashMap::from([("minecraft".to_string(), "1.20.1".to_string())]),
+        }
+    }
+
+    #[test]
+    fn side_follows_the_unsupported_side() {
+        assert_eq!(side_from_env(&None), (Side::Both, false));
+        assert_eq!(side_from_env(&env("required", "required")), (Side::Both, false));
+        assert_eq!(side_from_env(&env("unsupported", "required")), (Side::Server, false));
+        assert_eq!(side_from_env(&env("required", "unsupported")), (Side::Client, false));
+        assert_eq!(side_from_env(&env("optional", "unsupported")), (Side::Client, true));
+        assert_eq!(side_from_env(&env("optional", "optional")), (Side::Both, true));
+    }
+
+    #[test]
+    fn env_round_trips_through_side_from_env() {
+        for side in [Side::Both, Side::Client] {
+            for optional in [false, true] {
+                assert_eq!(side_from_env(&env_for(side, optional)), (side, optional));
+            }
+        }
+        assert_eq!(side_from_env(&env_for(Side::Server, false)), (Side::Server, false));
+    }
+
+    #[test]
+    fn mismatched_hashes_are_rejected() {
+        let bytes = b"mod jar".to_vec();
+        let good = mrpack_file("mods/a.jar", &[("sha1", sha1_bytes(&bytes)), ("sha512", sha512_bytes(&bytes))], &[]);
+        assert!(check_hashes(&good, &bytes).is_ok());
+        assert!(check_hashes(&good, b"tampered").is_err());
+
+        // sha512 wins over a matching sha1
+        let wrong_sha512 = mrpack_file("mods/a.jar", &[("sha1", sha1_bytes(&bytes)), ("sha512", sha512_bytes(b"other"))], &[]);
+        assert!(check_hashes(&wrong_sha512, &bytes).is_err());
+        let sha1_only = mrpack_file("mods/a.jar", &[("sha1", sha1_bytes(b"other"))], &[]);
+        assert!(check_hashes(&sha1_only, &bytes).is_err());
+        assert!(check_hashes(&mrpack_file("mods/a.jar", &[], &[]), &bytes).is_err());
+    }
+
+    #[tokio::test]
+    async fn resolve_only_follows_web_urls() {
+        let dir = temp_dir("resolve");
+        let jar = dir.join("a.jar");
+        fs::write(&jar, b"mod jar").unwrap();
+        let url = format!("file://{0}", jar.display());
+        let file = mrpack_file("mods/a.jar", &[("sha1", sha1_bytes(b"mod jar"))], &[url]);
+        let client = HttpSettings::default().client().unwrap();
+        let err = resolve_file(&client, &file).await.unwrap_err();
+        assert!(err.contains("only http(s)"), "{err}");
+
+        let unhashed = mrpack_file("mods/a.jar", &[], &["https://example.invalid/a.jar".to_string()]);
+        assert!(resolve_file(&client, &unhashed).await.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn side_overrides_are_applied_last() {
+        let dir = temp_dir("order");
+        let path = dir.join("pack.mrpack");
+        let overrides = vec![
+            ("config/a.toml".to_string(), Side::Client, b"client".to_vec()),
+            ("config/a.toml".to_string(), Side::Both, b"shared".to_vec()),
+            ("config/b.toml".to_string(), Side::Server, b"server".to_vec()),
+        ];
+        write_mrpack(&path, &index(vec![]), &overrides).unwrap();
+        let content = read_mrpack(&path).unwrap();
+        let sides: Vec<Side> = content.overrides.iter().map(|(_, side, _)| *side).collect();
+        assert_eq!(sides[0], Side::Both);
+
+        let mut files = vec![];
+        let mut notes = vec![];
+        apply_overrides(&mut files, content.overrides, &mut notes);
+        let a: Vec<&(PackFile, Vec<u8>)> = files.iter().filter(|(file, _)| file.remote_path() == "config/a.toml").collect();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].1, b"client".to_vec());
+        assert!(notes.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn import_downloads_files_from_the_host() {
+        let jar = b"minimap jar".to_vec();
+        let base = serve(HashMap::from([("/files/minimap.jar".to_string(), jar.clone())]));
+        let dir = temp_dir("import");
+        let path = dir.join("pack.mrpack");
+        let mut entry = mrpack_file("mods/minimap.jar", &[("sha1", sha1_bytes(&jar)), ("sha512", sha512_bytes(&jar))], &[format!("{base}/files/minimap.jar")]);
+        entry.env = env("optional", "unsupported");
+        let mut index = index(vec![entry]);
+        index.name = "Import Fixture".to_string();
+        index.dependencies.insert("forge".to_string(), "47.3.10".to_string());
+        let overrides = vec![
+            ("config/jei.toml".to_string(), Side::Client, b"client jei".to_vec()),
+            ("config/jei.toml".to_string(), Side::Both, b"jei".to_vec()),
+        ];
+        write_mrpack(&path, &index, &overrides).unwrap();
+
+        let report = import_mrpack(path).await.unwrap();
+        assert_eq!(report.pack_id, "local-import-fixture");
+        assert_eq!(report.file_count, 2);
+        assert!(report.notes.is_empty(), "{0:?}", report.notes);
+        let manifest = load_local_manifest(&report.pack_id).unwrap();
+        assert_eq!(manifest.minecraft_version.as_deref(), Some("1.20.1"));
+        assert_eq!(manifest.forge_version.as_deref(), Some("47.3.10"));
+        let minimap = manifest.files.iter().find(|file| file.name == "minimap.jar").unwrap();
+        assert_eq!((minimap.side, minimap.optional), (Side::Client, true));
+        assert_eq!(minimap.sha256.as_deref(), Some(sha256_bytes(&jar).as_str()));
+        let pack_dir = local_pack_dir(&report.pack_id).unwrap();
+        assert_eq!(fs::read(pack_dir.join("minimap.jar")).unwrap(), jar);
+        assert_eq!(fs::read(pack_dir.join("config/jei.toml")).unwrap(), b"client jei".to_vec());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn exported_instances_import_again() {
+        let jar = b"jei jar".to_vec();
+        let base = serve(HashMap::from([("/ExportFixture/jei.jar".to_string(), jar.clone())]));
+        let state_id = "ExportFixture";
+        let instance = instance_dir(state_id).unwrap();
+        fs::create_dir_all(instance.join("mods")).unwrap();
+        fs::create_dir_all(instance.join("config")).unwrap();
+        fs::write(instance.join("mods/jei.jar"), &jar).unwrap();
+        fs::write(instance.join("mods/extra.jar"), b"not from the pack").unwrap();
+        fs::write(instance.join("config/jei.toml"), b"jei").unwrap();
+
+        let mut jei = PackFile::new("jei.jar");
+        jei.sha256 = Some(sha256_bytes(&jar));
+        let mut minimap = PackFile::from_install_path("config/minimap.toml").unwrap();
+        minimap.side = Side::Client;
+        let manifest = PackManifest{
+            name: "Export Fixture".to_string(),
+            minecraft_version: Some("1.20.1".to_string()),
+            forge_version: Some("47.3.10".to_string()),
+            files: vec![jei, PackFile::from_install_path("config/jei.toml").unwrap(), minimap],
+            ..PackManifest::default()
+        };
+        manifest.save_installed(state_id).unwrap();
+        let installed = InstalledPack{ files: vec![
+            InstalledFile{ path: "mods/jei.jar".to_string(), sha256: sha256_bytes(&jar) },
+            InstalledFile{ path: "config/jei.toml".to_string(), sha256: sha256_bytes(b"jei") },
+        ]};
+        installed.save(state_id).unwrap();
+
+        let settings = LaunchSettings{
+            forge_version: "47.3.10".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            modpack: Modpack::Hosted(CatalogEntry{ id: "ExportFixture".to_string(), name: "Export Fixture".to_string() }),
+            state_id: state_id.to_string(),
+            quarantine_extras: false,
+            cache_limit_mb: 0,
+            is_cracked: false,
+            auth_username: "".to_string(),
+            auth_password: "".to_string(),
+            host_url: base,
+            host_mirrors: vec![],
+            http: HttpSettings::default(),
+            trusted_keys: vec![],
+        };
+        let dir = temp_dir("export");
+        let out_path = dir.join("export.mrpack");
+        // the config the pack lists but never installed is skipped, so is the user's jar
+        assert_eq!(export_mrpack(&settings, &instance, &out_path).unwrap(), 2);
+
+        let report = import_mrpack(out_path).await.unwrap();
+        assert_eq!(report.pack_id, "local-export-fixture");
+        assert_eq!(report.file_count, 2);
+        let manifest = load_local_manifest(&report.pack_id).unwrap();
+        let mut names: Vec<String> = manifest.files.iter().map(|file| file.remote_path()).collect();
+        names.sort();
+        assert_eq!(names, vec!["config/jei.toml", "jei.jar"]);
+        let pack_dir = local_pack_dir(&report.pack_id).unwrap();
+        assert_eq!(fs::read(pack_dir.join("jei.jar")).unwrap(), jar);
+        assert_eq!(fs::read(pack_dir.join("config/jei.toml")).unwrap(), b"jei".to_vec());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -11,13 +11,26 @@ use std::str::Utf8Error;
 use std::sync::Mutex;
 use std::time::Duration;
 use fs_extra::file;
-use lazy_async_promise::{send_data, set_finished, set_progress, unpack_result, LazyVecPromise, Message, Progress};
+use lazy_async_promise::{ImmediateValuePromise, send_data, set_finished, set_progress, unpack_result, LazyVecPromise, Message, Progress};
 use lazy_static::lazy_static;
 use rand::Rng;
 use crate::{App, Modpack};
+use crate::hashing::{sha256_bytes, sha256_file};
+use crate::cache::{link_cached, read_cached, store_cached, trim_cache};
+use crate::http::{auth_error, check_status, is_auth_rejected, is_plain_http, is_web_url, HostAuthKind, HostClients};
+use crate::signing::{key_id, verify_manifest, SIGNATURE_SUFFIX};
+use crate::throttle::throttle;
+use crate::history::{PackHistory, PackRevision};
+use crate::installed::{InstalledFile, InstalledPack};
 use crate::launch::LaunchSettings;
-use crate::log::{error, info,CallbackLog};
+use crate::local_pack::local_pack_dir;
+use crate::manifest::{PackFile, PackManifest, Side, UpdatePolicy};
+use crate::selection::ModSelection;
+use crate::update_check::{check_for_updates, UpdateStatus};
+use crate::log::{error, info, warn, CallbackLog};
 use reqwest::blocking;
+use reqwest::{Client, StatusCode};
+use crate::hosts::{default_catalog, CatalogEntry};
 use tokio::sync::mpsc::Sender;
 
 use serde::de::DeserializeOwned;
@@ -25,95 +38,448 @@ use zip_extract::ZipExtractError;
 
 const TEMP_PATH:&str = "tmp";
 const TEMP_MOD_PATH:&str = "tmp\\mod";
-pub(crate) const TEMP_DATA_PATH:&str = "tmp\\data.dat";
+const LOCAL_URL_PREFIX:&str = "file://";
 
-pub fn download_modpack(app:&mut App, modpack: Modpack, minecraft_path: String,launch_settings: &LaunchSettings) -> Result<(),String>{
+pub fn download_modpack(app:&mut App, modpack: Modpack, instance_path: String,launch_settings: &LaunchSettings) -> Result<(),String>{
     info(&format!("begin request for {0}",modpack.get_name()),app);
 
-    let url = format!("http://{0}:{1}/{2}",launch_settings.host_ip,launch_settings.host_port,launch_settings.modpack.get_server_identifier());
+    let url = pack_url(launch_settings);
 
     info(&format!("url: {}", url),app);
+    if is_plain_http(&url) {
+        warn(&format!("{0} is fetched over plain HTTP, anyone on the network can swap out its mods. Use an https:// host if the server supports it.",modpack.get_name()),app);
+    }
+    if is_plain_http(&url) && launch_settings.http.auth.kind != HostAuthKind::None {
+        warn("The pack server login is sent unencrypted over plain HTTP!",app);
+    }
+    let clients = launch_settings.http.clients(&url)?;
 
-    fs::write(Path::new(TEMP_DATA_PATH), format!("{}\n{}",url,minecraft_path)).unwrap();
-
-    let last_id = match app.prev_log_ids.len()<=0{
-        false => {
-            app.prev_log_ids[app.prev_log_ids.len() - 1]
-        }
-        true => {
-            0
+    let pack_id = launch_settings.state_id.clone();
+    let selection = ModSelection::load(&pack_id);
+    let pinned = PackHistory::load(&pack_id).pinned_revision().cloned();
+    if let Some(revision) = &pinned {
+        let uncached = revision.uncached_files();
+        if !uncached.is_empty() {
+            return Err(format!("revision {0} can not be reinstalled, {1} of its files are no longer cached ({2})",revision.short_hash(),uncached.len(),uncached.join(", ")));
         }
-    };
-
-
+        info(&format!("{0} is pinned to revision {1}, installing it from the local cache",modpack.get_name(),revision.short_hash()),app);
+    }
 
-    app.download_callback = Some(make_request_buffer_slice(TEMP_DATA_PATH,last_id));
+    app.callback_log_cursor = 0;
+    app.callback_finished = false;
+    app.mod_list = None;
+    app.download_callback = Some(make_request_buffer_slice(SyncJob{
+        urls: pack_urls(launch_settings),
+        clients,
+        trusted_keys: launch_settings.trusted_keys.clone(),
+        instance_path,
+        pack_id,
+        selection,
+        pinned,
+        cache_limit_mb: launch_settings.cache_limit_mb,
+    }));
 
     Ok(())
 }
 
+pub fn pack_url(launch_settings: &LaunchSettings) -> String{
+    match &launch_settings.modpack {
+        Modpack::Local(id) => {
+            let dir = local_pack_dir(id).map(|dir| dir.display().to_string()).unwrap_or_default();
+            format!("{LOCAL_URL_PREFIX}{dir}")
+        }
+        modpack => format!("{0}/{1}",launch_settings.host_url,modpack.get_server_identifier()),
+    }
+}
 
-fn make_request_buffer_slice(
-    file_path: &'static str,
-    last_id: u32,
-) -> LazyVecPromise<CallbackLog> {
-    let updater = move |tx: Sender<Message<CallbackLog>>| async move {
+/// The pack url on the host followed by the same pack on each mirror of the host.
+pub fn pack_urls(launch_settings: &LaunchSettings) -> Vec<String>{
+    let mut urls = vec![pack_url(launch_settings)];
+    if !matches!(launch_settings.modpack, Modpack::Local(_)) {
+        let pack_id = launch_settings.modpack.get_server_identifier();
+        urls.extend(launch_settings.host_mirrors.iter().map(|mirror| format!("{mirror}/{pack_id}")));
+    }
+    urls
+}
 
-        //get data
-        let binding = file::read_to_string(file_path).unwrap();
-        let data = binding.split('\n').collect::<Vec<&str>>();
-        let url = data[0];
-        let mc_path = data[1];
+/// Fetches a file from a pack host, or from disk for `file://` urls of local packs.
+pub(crate) async fn fetch_bytes(client: &Client, url: &str) -> Result<Vec<u8>, String>{
+    if let Some(path) = url.strip_prefix(LOCAL_URL_PREFIX) {
+        return match fs::read(path) {
+            Ok(bytes) => Ok(bytes),
+            Err(err) => Err(format!("{path}: {err}")),
+        };
+    }
+    let mut response = match client.get(url).send().await {
+        Ok(response) => check_status(response)?,
+        Err(err) => return Err(err.to_string()),
+    };
+    let mut bytes = Vec::with_capacity(response.content_length().unwrap_or(0) as usize);
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                throttle(chunk.len()).await;
+                bytes.extend_from_slice(&chunk);
+            }
+            Ok(None) => return Ok(bytes),
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+}
 
-        unpack_result!(clear_folder(format!("{0}/mods",data[1])),tx);
+/// Fetches the pack manifest in the background, so the UI can show optional mods before syncing.
+pub fn start_manifest_fetch(launch_settings: &LaunchSettings) -> ImmediateValuePromise<Result<PackManifest,String>>{
+    let urls = pack_urls(launch_settings);
+    let http = launch_settings.http.clone();
+    let trusted_keys = launch_settings.trusted_keys.clone();
+    ImmediateValuePromise::new(async move {
+        let clients = match http.clients(&urls[0]) {
+            Ok(clients) => clients,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(fetch_manifest(&clients, &urls, &trusted_keys).await)
+    })
+}
 
-        //get metadata
-        let response = unpack_result!(reqwest::get(format!("{url}/metadata")).await, tx);
-        let text = unpack_result!(response.text().await,tx);
-        let files = text.split("\n").collect::<Vec<&str>>();
-        if files.len()==0 {
-            send_data!(CallbackLog{
-                data: "no mods were found for this pack!".parse().unwrap(),
-                id: last_id,
-            }, tx);
-            set_finished!(tx);
+/// Compares the host manifest with the installed pack in the background, nothing is downloaded.
+pub fn start_update_check(launch_settings: &LaunchSettings) -> ImmediateValuePromise<Result<UpdateStatus,String>>{
+    let urls = pack_urls(launch_settings);
+    let pack_id = launch_settings.state_id.clone();
+    let http = launch_settings.http.clone();
+    let trusted_keys = launch_settings.trusted_keys.clone();
+    ImmediateValuePromise::new(async move {
+        // LAUNCH reinstalls the pinned revision, so the host does not matter
+        if let Some(revision) = PackHistory::load(&pack_id).pinned_revision() {
+            return Ok(Ok(UpdateStatus{pinned: Some(revision.short_hash().to_string()), ..Default::default()}));
         }
+        let clients = match http.clients(&urls[0]) {
+            Ok(clients) => clients,
+            Err(err) => return Ok(Err(err)),
+        };
+        let manifest = match fetch_manifest(&clients, &urls, &trusted_keys).await {
+            Ok(manifest) => manifest,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(Ok(check_for_updates(&manifest, &InstalledPack::load(&pack_id), &ModSelection::load(&pack_id))))
+    })
+}
 
-        let total_requests = (files.len()+1) as f64;
+/// Everything a sync needs, cloned into the background task.
+#[derive(Clone)]
+struct SyncJob{
+    urls:Vec<String>,
+    clients:HostClients,
+    trusted_keys:Vec<String>,
+    instance_path:String,
+    pack_id:String,
+    selection:ModSelection,
+    pinned:Option<PackRevision>,
+    cache_limit_mb:u64,
+}
 
-        send_data!(CallbackLog{
-                data: format!("starting download for {0} files",files.len()),
-                id: last_id,
-            }, tx);
-        set_progress!(
-            Progress::from_fraction(1, total_requests),
-            tx
-        );
+/// Fetches the pack list of the host from `packs.json`, hosts without one serve the built in packs.
+pub fn start_catalog_fetch(launch_settings: &LaunchSettings) -> ImmediateValuePromise<Result<Vec<CatalogEntry>,String>>{
+    let url = format!("{0}/packs.json", launch_settings.host_url);
+    let http = launch_settings.http.clone();
+    ImmediateValuePromise::new(async move {
+        let client = match http.client() {
+            Ok(client) => client,
+            Err(err) => return Ok(Err(err)),
+        };
+        let response = match client.get(&url).send().await {
+            Ok(response) => response,
+            Err(err) => return Ok(Err(err.to_string())),
+        };
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Ok(default_catalog()));
+        }
+        let response = match check_status(response) {
+            Ok(response) => response,
+            Err(err) => return Ok(Err(err)),
+        };
+        let mut catalog = match response.json::<Vec<CatalogEntry>>().await {
+            Ok(catalog) => catalog,
+            Err(err) => return Ok(Err(format!("{url}: {err}"))),
+        };
+        for entry in catalog.iter().filter(|entry| !entry.is_valid()) {
+            log::warn!("{url} lists a pack with the invalid id '{0}', skipping it", entry.id);
+        }
+        catalog.retain(|entry| entry.is_valid());
+        Ok(Ok(catalog))
+    })
+}
 
-        let mut c = 1;
-        for file in files {
-            if file=="" {
-                continue;
+fn make_request_buffer_slice(job: SyncJob) -> LazyVecPromise<CallbackLog> {
+    let updater = move |tx: Sender<Message<CallbackLog>>| {
+        let SyncJob{ urls, clients, trusted_keys, instance_path, pack_id, selection, pinned, cache_limit_mb } = job.clone();
+        async move {
+            let manifest = match &pinned {
+                Some(revision) => revision.manifest.clone(),
+                None => unpack_result!(fetch_manifest(&clients, &urls, &trusted_keys).await, tx),
+            };
+            let mut mirrors = urls.clone();
+            for mirror in &manifest.mirrors {
+                let mirror = mirror.trim_end_matches('/').to_string();
+                // the manifest comes from the network, it may not point at local files
+                if !is_web_url(&mirror) {
+                    send_data!(CallbackLog::warn(format!("ignoring mirror {mirror}, only http(s) mirrors are allowed")), tx);
+                    continue;
+                }
+                if !mirrors.contains(&mirror) {
+                    mirrors.push(mirror);
+                }
             }
-            let response = unpack_result!(reqwest::get(format!("{url}/{file}")).await, tx);
-            let bytes = unpack_result!(response.bytes().await,tx);
-            let mut out_file = unpack_result!(File::create(format!("{mc_path}/mods/{file}")),tx);
-            unpack_result!(out_file.write_all(&bytes),tx);
-            send_data!(CallbackLog{
-                data: format!("downloaded file: {file}"),
-                id: last_id+c,
-            }, tx);
+            let client_files = manifest.files_for_side(Side::Client);
+            let server_only = manifest.files.len() - client_files.len();
+            if server_only > 0 {
+                send_data!(CallbackLog::info(format!("skipping {server_only} server-only files")), tx);
+            }
+            let files = client_files.into_iter()
+                .filter(|file| selection.is_enabled(file))
+                .collect::<Vec<&PackFile>>();
+            let skipped = manifest.files.len() - server_only - files.len();
+            if skipped > 0 {
+                send_data!(CallbackLog::info(format!("skipping {skipped} optional files that are turned off")), tx);
+            }
+            if files.is_empty() {
+                send_data!(CallbackLog::warn("no mods were found for this pack!".to_string()), tx);
+                set_finished!(tx);
+                return;
+            }
+
+            // refuse the whole pack before touching anything if one path is unsafe
+            for file in &files {
+                unpack_result!(file.install_path(), tx);
+            }
+
+            let total_requests = (files.len()+1) as f64;
+
+            send_data!(CallbackLog::info(format!("starting download for {0} files",files.len())), tx);
             set_progress!(
-                Progress::from_fraction(c, total_requests),
+                Progress::from_fraction(1, total_requests),
                 tx
             );
-            c+=1;
+
+            let previous_install = InstalledPack::load(&pack_id);
+            let mut installed_files = vec![];
+            let mut c = 1;
+            for file in &files {
+                let install_path = unpack_result!(file.install_path(), tx);
+                let install_key = install_path.to_string_lossy().replace('\\', "/");
+                let out_path = Path::new(&instance_path).join(&install_path);
+
+                let cached = match &pinned {
+                    Some(revision) => Some(unpack_result!(revision.read_cached_file(&install_key), tx)),
+                    None => file.sha256.as_ref().and_then(|sha256| read_cached(sha256)),
+                };
+                let from_cache = cached.is_some();
+                let (bytes, source) = match cached {
+                    Some(bytes) => (bytes, "cache".to_string()),
+                    None => unpack_result!(fetch_from_mirrors(&clients, &mut mirrors, &file.remote_path(), file.sha256.as_deref()).await, tx),
+                };
+                let sha256 = sha256_bytes(&bytes);
+                if let Some(expected) = &file.sha256 {
+                    if *expected != sha256 {
+                        set_error!(format!("{install_key} does not match the hash in the pack manifest"), tx);
+                        return;
+                    }
+                }
+                if !from_cache {
+                    if let Err(err) = store_cached(&sha256, &bytes) {
+                        send_data!(CallbackLog::warn(format!("could not cache {install_key}: {err}")), tx);
+                    }
+                }
+
+                let mut write_path = out_path.clone();
+                if file.policy == UpdatePolicy::Preserve && out_path.exists() {
+                    let local_hash = unpack_result!(sha256_file(&out_path),tx);
+                    let user_modified = local_hash != sha256 && previous_install.installed_hash(&install_key) != Some(local_hash.as_str());
+                    if user_modified {
+                        let mut side_name = out_path.file_name().unwrap_or_default().to_os_string();
+                        side_name.push(".new");
+                        write_path = out_path.with_file_name(side_name);
+                        send_data!(CallbackLog::warn(format!("kept your changes to {0}, the pack's version was saved as {1}", install_key, write_path.display())), tx);
+                    }
+                }
+
+                if let Some(parent) = write_path.parent() {
+                    unpack_result!(fs::create_dir_all(parent),tx);
+                }
+                // jars are never edited in place, so they can share the cached copy
+                let linked = file.target == "mods" && write_path == out_path && link_cached(&sha256, &write_path).is_ok();
+                if !linked {
+                    let mut out_file = unpack_result!(File::create(&write_path),tx);
+                    unpack_result!(out_file.write_all(&bytes),tx);
+                }
+                if write_path == out_path {
+                    match from_cache {
+                        true => send_data!(CallbackLog::info(format!("installed file from cache: {install_key}")), tx),
+                        false => send_data!(CallbackLog::info(format!("downloaded file: {install_key} from {source}")), tx),
+                    }
+                }
+                installed_files.push(InstalledFile{ path: install_key, sha256 });
+
+                set_progress!(
+                    Progress::from_fraction(c, total_requests),
+                    tx
+                );
+                c+=1;
+            }
+
+            for log in remove_dropped_files(Path::new(&instance_path), &previous_install, &installed_files) {
+                send_data!(log, tx);
+            }
+
+            if pinned.is_none() {
+                let revision = PackRevision::new(&manifest, installed_files.clone());
+                if let Err(err) = PackHistory::load(&pack_id).record(&pack_id, revision) {
+                    send_data!(CallbackLog::warn(format!("could not record pack revision: {err}")), tx);
+                }
+            }
+            // after recording, so the files of the new revision are kept
+            match trim_cache(cache_limit_mb) {
+                Ok(0) => {}
+                Ok(freed) => send_data!(CallbackLog::info(format!("trimmed {0} MB from the download cache", freed / 1024 / 1024)), tx),
+                Err(err) => send_data!(CallbackLog::warn(format!("could not trim the download cache: {err}")), tx),
+            }
+            let installed = InstalledPack{
+                files: installed_files,
+            };
+            if let Err(err) = installed.save(&pack_id).and_then(|_| manifest.save_installed(&pack_id)) {
+                send_data!(CallbackLog::warn(format!("could not record installed files: {err}")), tx);
+            }
+            set_finished!(tx);
         }
-        set_finished!(tx);
     };
     LazyVecPromise::new(updater, 6)
 }
 
+/// Removes the files the pack dropped since the last sync, unless the user changed them.
+/// Anything the pack never installed, like jars the user added to `mods`, is left alone.
+fn remove_dropped_files(instance_path: &Path, previous_install: &InstalledPack, installed_files: &[InstalledFile]) -> Vec<CallbackLog>{
+    let mut logs = vec![];
+    for old in &previous_install.files {
+        if installed_files.iter().any(|file| file.path == old.path) {
+            continue;
+        }
+        let install_path = match PackFile::from_install_path(&old.path).map(|file| file.install_path()) {
+            Some(Ok(install_path)) => install_path,
+            _ => continue,
+        };
+        let old_path = instance_path.join(install_path);
+        if !old_path.exists() {
+            continue;
+        }
+        match sha256_file(&old_path) {
+            Ok(hash) if hash == old.sha256 => match fs::remove_file(&old_path) {
+                Ok(_) => logs.push(CallbackLog::info(format!("removed file: {0}", old.path))),
+                Err(err) => logs.push(CallbackLog::warn(format!("could not remove {0}: {err}", old.path))),
+            },
+            _ => logs.push(CallbackLog::warn(format!("kept {0}, it was dropped from the pack but you changed it", old.path))),
+        }
+    }
+    logs
+}
+
+/// Fetches a pack file from the first mirror that serves it with the expected hash. A mirror
+/// that works is moved to the front, so the rest of the sync does not wait on a dead host.
+/// Returns the bytes and the mirror that served them.
+async fn fetch_from_mirrors(clients: &HostClients, mirrors: &mut Vec<String>, remote_path: &str, sha256: Option<&str>) -> Result<(Vec<u8>, String), String>{
+    let mut errors = vec![];
+    for index in 0..mirrors.len() {
+        let url = format!("{0}/{remote_path}", mirrors[index]);
+        log::debug!("requesting {url}");
+        let bytes = match fetch_bytes(clients.for_url(&url), &url).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                log::warn!("{url}: {err}");
+                errors.push(format!("{url}: {err}"));
+                continue;
+            }
+        };
+        if sha256.is_some_and(|sha256| sha256 != sha256_bytes(&bytes)) {
+            log::warn!("{url} does not match the hash in the pack manifest");
+            errors.push(format!("{url}: hash mismatch"));
+            continue;
+        }
+        let mirror = mirrors.remove(index);
+        mirrors.insert(0, mirror.clone());
+        return Ok((bytes, mirror));
+    }
+    Err(errors.join(", "))
+}
+
+/// Fetches the manifest from the first pack url that serves a valid one.
+async fn fetch_manifest(clients: &HostClients, urls: &[String], trusted_keys: &[String]) -> Result<PackManifest, String>{
+    let mut errors = vec![];
+    for url in urls {
+        match fetch_manifest_from(clients.for_url(url), url, trusted_keys).await {
+            Ok(manifest) => {
+                if !errors.is_empty() {
+                    log::info!("manifest served by mirror {url}");
+                }
+                return Ok(manifest);
+            }
+            Err(err) => {
+                log::warn!("{url}: {err}");
+                errors.push(format!("{url}: {err}"));
+            }
+        }
+    }
+    Err(errors.join(", "))
+}
+
+/// Fetches `manifest.json` from the pack url, falling back to the plain `metadata` file list
+/// for hosts that do not serve a manifest yet. With trusted keys the manifest must come with
+/// a valid `manifest.json.sig`.
+async fn fetch_manifest_from(client: &Client, url: &str, trusted_keys: &[String]) -> Result<PackManifest, String>{
+    if url.starts_with(LOCAL_URL_PREFIX) {
+        let bytes = fetch_bytes(client, &format!("{url}/manifest.json")).await?;
+        return PackManifest::parse(&String::from_utf8_lossy(&bytes));
+    }
+    let response = match client.get(format!("{url}/manifest.json")).send().await {
+        Ok(response) => response,
+        Err(err) => return Err(err.to_string()),
+    };
+    if response.status().is_success() {
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(err) => return Err(err.to_string()),
+        };
+        if !trusted_keys.is_empty() {
+            let signature = match fetch_bytes(client, &format!("{url}/manifest.json{SIGNATURE_SUFFIX}")).await {
+                Ok(signature) => signature,
+                Err(err) => return Err(format!("the manifest is not signed ({err}), expected a signature by one of: {0}", trusted_keys.iter().map(|key| key_id(key)).collect::<Vec<&str>>().join(", "))),
+            };
+            let key = verify_manifest(&bytes, &String::from_utf8_lossy(&signature), trusted_keys)?;
+            log::info!("manifest signature verified with key {0}", key_id(key));
+            // the signature only covers the files through their hashes
+            let manifest = PackManifest::parse(&String::from_utf8_lossy(&bytes))?;
+            if let Some(file) = manifest.files.iter().find(|file| file.sha256.is_none()) {
+                return Err(format!("{0} has no sha256 in the signed manifest, every file of a signed pack needs one", file.name));
+            }
+            return Ok(manifest);
+        }
+        return PackManifest::parse(&String::from_utf8_lossy(&bytes));
+    }
+    if is_auth_rejected(response.status()) {
+        return Err(auth_error(response.status()));
+    }
+    if !trusted_keys.is_empty() {
+        return Err(format!("{url} has no manifest.json ({0}) and the file list cannot be signed", response.status()));
+    }
+
+    log::debug!("{url} has no manifest.json ({0}), using the metadata file list", response.status());
+    let response = match client.get(format!("{url}/metadata")).send().await {
+        Ok(response) => check_status(response)?,
+        Err(err) => return Err(err.to_string()),
+    };
+    match response.text().await {
+        Ok(text) => Ok(PackManifest::from_file_list(&text)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 fn copy_folder(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()>{
     for entry in fs::read_dir(src)? {
         let entry = entry?;
@@ -127,24 +493,113 @@ fn copy_folder(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()>{
     Ok(())
 }
 
-fn clear_folder(path:String) -> Result<(),String>{
-    match fs::remove_dir_all(&path){
-        Ok(_) => {}
-        Err(err) => {
-            return Err(err.to_string())
-        }
-    }
-    match fs::create_dir(&path){
-        Ok(_) => {}
-        Err(err) => {
-            return Err(err.to_string())
-        }
-    }
-    Ok(())
-}
 
 pub(crate) fn setup_temp_folder() -> Result<(), Box<dyn Error>>{
     fs::create_dir(TEMP_PATH)?;
     fs::create_dir(TEMP_MOD_PATH)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+    use crate::hashing::sha256_file;
+    use crate::installed::{InstalledFile, InstalledPack};
+    use std::collections::HashMap;
+    use crypto::ed25519;
+    use crate::http::HttpSettings;
+    use crate::mods::{find_mod_issues, scan_mods_folder};
+    use crate::test_server::serve;
+    use super::{fetch_manifest_from, remove_dropped_files};
+
+    fn temp_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("qmodmanager-pack-{name}-{0}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("mods")).unwrap();
+        dir
+    }
+
+    /// Writes a jar declaring `mod_id`, `salt` keeps jars of the same mod from being identical.
+    fn write_jar(path: &Path, mod_id: &str, salt: &str){
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        zip.start_file("META-INF/mods.toml", SimpleFileOptions::default()).unwrap();
+        write!(zip, "[[mods]]\nmodId = \"{mod_id}\"\nversion = \"1.0.0\"\n# {salt}\n").unwrap();
+        zip.finish().unwrap();
+    }
+
+    fn installed(instance: &Path, paths: &[&str]) -> Vec<InstalledFile>{
+        paths.iter()
+            .map(|path| InstalledFile{ path: path.to_string(), sha256: sha256_file(&instance.join(path)).unwrap() })
+            .collect()
+    }
+
+    #[test]
+    fn local_extras_survive_a_sync_and_are_reported() {
+        let instance = temp_dir("extras");
+        let mods = instance.join("mods");
+        write_jar(&mods.join("jei.jar"), "jei", "pack");
+        write_jar(&mods.join("dropped.jar"), "dropped", "pack");
+        write_jar(&mods.join("jei-old.jar"), "jei", "user");
+        let previous = InstalledPack{ files: installed(&instance, &["mods/jei.jar", "mods/dropped.jar"]) };
+        let current = installed(&instance, &["mods/jei.jar"]);
+
+        let logs = remove_dropped_files(&instance, &previous, &current);
+        assert_eq!(logs.len(), 1, "{logs:?}");
+        assert!(!mods.join("dropped.jar").exists());
+        assert!(mods.join("jei-old.jar").exists());
+
+        let jars = scan_mods_folder(&mods, &InstalledPack{ files: current }).unwrap();
+        let extra = jars.iter().find(|jar| jar.file_name == "jei-old.jar").unwrap();
+        assert!(!extra.from_pack);
+        let issues = find_mod_issues(&jars, &[]);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].extras, vec![mods.join("jei-old.jar")]);
+        fs::remove_dir_all(instance).unwrap();
+    }
+
+    #[test]
+    fn changed_files_dropped_from_the_pack_are_kept() {
+        let instance = temp_dir("changed");
+        fs::create_dir_all(instance.join("config")).unwrap();
+        fs::write(instance.join("config/old.toml"), "pack").unwrap();
+        let previous = InstalledPack{ files: installed(&instance, &["config/old.toml"]) };
+        fs::write(instance.join("config/old.toml"), "edited").unwrap();
+
+        let logs = remove_dropped_files(&instance, &previous, &[]);
+        assert_eq!(logs.len(), 1);
+        assert!(instance.join("config/old.toml").exists());
+        fs::remove_dir_all(instance).unwrap();
+    }
+
+    /// Serves `manifest` signed by a fresh key and returns the pack url and the key.
+    fn signed_host(manifest: &str) -> (String, String){
+        let (secret, public) = ed25519::keypair(&[7; 32]);
+        let hex = |bytes: &[u8]| bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+        let signature = hex(&ed25519::signature(manifest.as_bytes(), &secret));
+        let base = serve(HashMap::from([
+            ("/Signed/manifest.json".to_string(), manifest.as_bytes().to_vec()),
+            ("/Signed/manifest.json.sig".to_string(), signature.into_bytes()),
+        ]));
+        (format!("{base}/Signed"), hex(&public))
+    }
+
+    #[tokio::test]
+    async fn signed_manifests_need_a_hash_for_every_file() {
+        let client = HttpSettings::default().client().unwrap();
+        let hash = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+        let (url, key) = signed_host(&format!(r#"{{"files": [{{"name": "jei.jar", "sha256": "{hash}"}}]}}"#));
+        let manifest = fetch_manifest_from(&client, &url, &[key]).await.unwrap();
+        assert_eq!(manifest.files.len(), 1);
+
+        let (url, key) = signed_host(&format!(r#"{{"files": [{{"name": "jei.jar", "sha256": "{hash}"}}, {{"name": "extra.jar"}}]}}"#));
+        let err = fetch_manifest_from(&client, &url, std::slice::from_ref(&key)).await.unwrap_err();
+        assert!(err.contains("extra.jar has no sha256"), "{err}");
+        // without trusted keys the same manifest is accepted
+        assert!(fetch_manifest_from(&client, &url, &[]).await.is_ok());
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::manifest::PackFile;
+use crate::storage::{pack_dir, read_json, write_json};
+
+const SELECTION_FILE:&str = "selection.json";
+
+/// The optional files a user turned on or off for a pack. Files the user never touched
+/// follow the default from the pack manifest.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ModSelection{
+    pub enabled:HashMap<String, bool>,
+}
+
+impl ModSelection{
+    pub fn load(pack_id: &str) -> ModSelection{
+        match pack_dir(pack_id) {
+            Ok(dir) => read_json(&dir.join(SELECTION_FILE)).unwrap_or_default(),
+            Err(_) => ModSelection::default(),
+        }
+    }
+
+    pub fn save(&self, pack_id: &str) -> Result<(), String>{
+        write_json(&pack_dir(pack_id)?.join(SELECTION_FILE), self)
+    }
+
+    pub fn is_enabled(&self, file: &PackFile) -> bool{
+        if !file.optional {
+            return true;
+        }
+        *self.enabled.get(&file.name).unwrap_or(&file.enabled_by_default)
+    }
+
+    pub fn set_enabled(&mut self, file: &PackFile, enabled: bool){
+        self.enabled.insert(file.name.clone(), enabled);
+    }
+}
@@ -0,0 +1,100 @@
+use crypto::ed25519;
+
+pub(crate) const SIGNATURE_SUFFIX:&str = ".sig";
+
+fn decode_hex(text: &str) -> Option<Vec<u8>>{
+    let text = text.trim();
+    // from_str_radix would also take a sign
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+/// Short form of a key for log messages.
+pub fn key_id(key: &str) -> &str{
+    match key.char_indices().nth(16) {
+        Some((end, _)) => &key[..end],
+        None => key,
+    }
+}
+
+/// Checks a hex encoded detached signature over the manifest bytes against the trusted keys.
+/// Returns the key that signed the manifest.
+pub fn verify_manifest<'a>(manifest: &[u8], signature: &str, keys: &'a [String]) -> Result<&'a str, String>{
+    let expected = keys.iter().map(|key| key_id(key)).collect::<Vec<&str>>().join(", ");
+    let signature = match decode_hex(signature) {
+        Some(signature) if signature.len() == 64 => signature,
+        _ => return Err(format!("the manifest signature is malformed, expected a hex ed25519 signature by one of: {expected}")),
+    };
+    for key in keys {
+        let public_key = match decode_hex(key) {
+            Some(public_key) if public_key.len() == 32 => public_key,
+            _ => {
+                log::warn!("ignoring malformed trusted key {0}", key_id(key));
+                continue;
+            }
+        };
+        if ed25519::verify(manifest, &public_key, &signature) {
+            return Ok(key);
+        }
+    }
+    Err(format!("the manifest signature does not match any trusted key, expected one of: {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::ed25519;
+    use super::{key_id, verify_manifest};
+
+    fn hex(bytes: &[u8]) -> String{
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Returns the hex public key and the hex signature of `manifest` for a key made from `seed`.
+    fn sign(seed: u8, manifest: &[u8]) -> (String, String){
+        let (secret, public) = ed25519::keypair(&[seed; 32]);
+        (hex(&public), hex(&ed25519::signature(manifest, &secret)))
+    }
+
+    const MANIFEST:&[u8] = br#"{"name": "Signed", "files": []}"#;
+
+    #[test]
+    fn valid_signatures_pass() {
+        let (key, signature) = sign(1, MANIFEST);
+        let (other, _) = sign(2, MANIFEST);
+        let keys = vec![other, key.clone()];
+        assert_eq!(verify_manifest(MANIFEST, &signature, &keys).unwrap(), key);
+        assert_eq!(verify_manifest(MANIFEST, &signature.to_uppercase(), &keys).unwrap(), key);
+    }
+
+    #[test]
+    fn tampered_manifests_fail() {
+        let (key, signature) = sign(1, MANIFEST);
+        let mut tampered = MANIFEST.to_vec();
+        tampered[10] ^= 1;
+        assert!(verify_manifest(&tampered, &signature, &[key]).is_err());
+    }
+
+    #[test]
+    fn untrusted_keys_fail() {
+        let (_, signature) = sign(1, MANIFEST);
+        let (trusted, _) = sign(2, MANIFEST);
+        assert!(verify_manifest(MANIFEST, &signature, &[trusted]).is_err());
+        assert!(verify_manifest(MANIFEST, &signature, &[]).is_err());
+    }
+
+    #[test]
+    fn malformed_hex_is_rejected() {
+        let (key, signature) = sign(1, MANIFEST);
+        for bad in [&signature[1..], &signature[..64], "", "zz", &format!("+{0}", &signature[1..])] {
+            assert!(verify_manifest(MANIFEST, bad, std::slice::from_ref(&key)).is_err(), "{bad}");
+        }
+        for bad in [&key[2..], "not a key", &format!("+{0}", &key[1..]), "ä€ключключключключключ"] {
+            assert!(verify_manifest(MANIFEST, &signature, &[bad.to_string()]).is_err(), "{bad}");
+        }
+        assert_eq!(key_id("ä€ключключключключключ").chars().count(), 16);
+    }
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Returns the platform data directory for QModManager, creating it when missing
+/// (`%APPDATA%\QModManager\data` on windows).
+pub fn data_dir() -> Result<PathBuf, String>{
+    let path = match platform_data_dir() {
+        Some(path) => path,
+        None => return Err("could not find the platform data directory".to_string()),
+    };
+    match fs::create_dir_all(&path) {
+        Ok(_) => Ok(path),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(not(test))]
+fn platform_data_dir() -> Option<PathBuf>{
+    directories::ProjectDirs::from("", "", "QModManager").map(|dirs| dirs.data_dir().to_path_buf())
+}
+
+/// Tests never touch the user's packs, they get a data directory of their own.
+#[cfg(test)]
+fn platform_data_dir() -> Option<PathBuf>{
+    Some(std::env::temp_dir().join(format!("qmodmanager-test-data-{0}", std::process::id())))
+}
+
+/// Returns a subdirectory of the data directory, creating it when missing.
+pub fn data_subdir(name: &str) -> Result<PathBuf, String>{
+    let path = data_dir()?.join(name);
+    match fs::create_dir_all(&path) {
+        Ok(_) => Ok(path),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Returns the directory that holds the local state of a pack, creating it when missing.
+pub fn pack_dir(pack_id: &str) -> Result<PathBuf, String>{
+    data_subdir(&format!("packs/{pack_id}"))
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String>{
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => return Err(format!("{0}: {err}", path.display())),
+    };
+    match serde_json::from_str(&text) {
+        Ok(value) => Ok(value),
+        Err(err) => Err(format!("{0}: {err}", path.display())),
+    }
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String>{
+    let text = match serde_json::to_string_pretty(value) {
+        Ok(text) => text,
+        Err(err) => return Err(err.to_string()),
+    };
+    match fs::write(path, text) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{0}: {err}", path.display())),
+    }
+}
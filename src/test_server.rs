@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::thread;
+
+/// A pack host stand-in for tests: serves `files` by path on a loopback port until the test
+/// process exits. Returns the base url, e.g. `http://127.0.0.1:40123`.
+pub fn serve(files: HashMap<String, Vec<u8>>) -> String{
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let url = format!("http://{0}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            // skip the headers, the stand-in only looks at the path
+            let mut line = String::new();
+            while reader.read_line(&mut line).map(|read| read > 2).unwrap_or(false) {
+                line.clear();
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or("/");
+            let mut stream = &stream;
+            let _ = match files.get(path) {
+                Some(bytes) => write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {0}\r\nConnection: close\r\n\r\n", bytes.len())
+                    .and_then(|_| stream.write_all(bytes)),
+                None => write!(stream, "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+            };
+        }
+    });
+    url
+}
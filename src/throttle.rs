@@ -0,0 +1,37 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use lazy_static::lazy_static;
+
+/// Shared by every download, so the limit holds for the whole app and not per transfer.
+struct RateLimiter{
+    bytes_per_sec:u64,
+    /// When the bytes handed out so far have been paid for at the limit.
+    next_free:Instant,
+}
+
+lazy_static! {
+    static ref LIMITER: Mutex<RateLimiter> = Mutex::new(RateLimiter{ bytes_per_sec: 0, next_free: Instant::now() });
+}
+
+/// Sets the download limit in KiB per second, 0 turns it off.
+pub fn set_download_limit(kib_per_sec: u32){
+    if let Ok(mut limiter) = LIMITER.lock() {
+        limiter.bytes_per_sec = kib_per_sec as u64 * 1024;
+        limiter.next_free = Instant::now();
+    }
+}
+
+/// Waits until `len` more bytes fit in the limit. Not reading the response while waiting
+/// makes the host slow down as well.
+pub async fn throttle(len: usize){
+    let wait = match LIMITER.lock() {
+        Ok(mut limiter) if limiter.bytes_per_sec > 0 => {
+            let now = Instant::now();
+            let start = limiter.next_free.max(now);
+            limiter.next_free = start + Duration::from_secs_f64(len as f64 / limiter.bytes_per_sec as f64);
+            limiter.next_free - now
+        }
+        _ => return,
+    };
+    tokio::time::sleep(wait).await;
+}
@@ -0,0 +1,149 @@
+use crate::cache::is_cached;
+use crate::installed::InstalledPack;
+use crate::manifest::{PackManifest, Side};
+use crate::selection::ModSelection;
+
+/// How the pack on the host differs from what the last sync installed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UpdateStatus{
+    /// Files that are new or whose hash changed.
+    pub changed:usize,
+    /// Files that were installed before but are no longer part of the pack.
+    pub removed:usize,
+    /// Bytes that would have to be downloaded, files already in the cache are free.
+    pub download_bytes:u64,
+    /// Installed files the host lists without a hash, these can only be checked by syncing.
+    pub unverified:usize,
+    /// Short hash of the revision the pack is pinned to, pinned packs do not follow the host.
+    pub pinned:Option<String>,
+}
+
+impl UpdateStatus{
+    pub fn is_up_to_date(&self) -> bool{
+        self.changed == 0 && self.removed == 0
+    }
+
+    pub fn describe(&self) -> String{
+        if let Some(hash) = &self.pinned {
+            return format!("pinned to {hash}");
+        }
+        let mut text = match self.is_up_to_date() {
+            true => "up to date".to_string(),
+            false => {
+                let mut parts = vec![];
+                if self.changed > 0 {
+                    parts.push(format!("{0} files to update ({1:.1} MB)", self.changed, self.download_bytes as f64 / 1024.0 / 1024.0));
+                }
+                if self.removed > 0 {
+                    parts.push(format!("{0} files to remove", self.removed));
+                }
+                parts.join(", ")
+            }
+        };
+        if self.unverified > 0 {
+            text.push_str(&format!(" ({0} files without hashes not checked)", self.unverified));
+        }
+        text
+    }
+}
+
+/// Compares the host manifest against the installed record of a pack without downloading anything.
+pub fn check_for_updates(manifest: &PackManifest, installed: &InstalledPack, selection: &ModSelection) -> UpdateStatus{
+    let mut status = UpdateStatus::default();
+    let mut wanted = vec![];
+    for file in manifest.files_for_side(Side::Client) {
+        if !selection.is_enabled(file) {
+            continue;
+        }
+        let path = match file.install_path() {
+            Ok(path) => path.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        let changed = match (&file.sha256, installed.installed_hash(&path)) {
+            (_, None) => true,
+            (Some(expected), Some(current)) => expected != current,
+            (None, Some(_)) => {
+                status.unverified += 1;
+                false
+            }
+        };
+        if changed {
+            status.changed += 1;
+            let cached = file.sha256.as_deref().map(is_cached).unwrap_or(false);
+            if !cached {
+                status.download_bytes += file.size.unwrap_or(0);
+            }
+        }
+        wanted.push(path);
+    }
+    status.removed = installed.files.iter().filter(|file| !wanted.contains(&file.path)).count();
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::store_cached;
+    use crate::hashing::sha256_bytes;
+    use crate::installed::InstalledFile;
+    use crate::manifest::PackFile;
+
+    fn file(name: &str, sha256: Option<&str>, size: u64) -> PackFile{
+        let mut file = PackFile::new(name);
+        file.sha256 = sha256.map(|hash| hash.to_string());
+        file.size = Some(size);
+        file
+    }
+
+    fn installed(files: &[(&str, &str)]) -> InstalledPack{
+        InstalledPack{
+            files: files.iter().map(|(path, sha256)| InstalledFile{path: path.to_string(), sha256: sha256.to_string()}).collect(),
+        }
+    }
+
+    #[test]
+    fn changed_removed_and_unverified_files_are_counted(){
+        let manifest = PackManifest{
+            files: vec![
+                file("same.jar", Some("aaaa"), 10),
+                file("updated.jar", Some("bbbb"), 20),
+                file("new.jar", Some("cccc"), 30),
+                file("unhashed.jar", None, 40),
+            ],
+            ..Default::default()
+        };
+        let installed = installed(&[
+            ("mods/same.jar", "aaaa"),
+            ("mods/updated.jar", "old"),
+            ("mods/unhashed.jar", "dddd"),
+            ("mods/dropped.jar", "eeee"),
+        ]);
+        let status = check_for_updates(&manifest, &installed, &ModSelection::default());
+        assert_eq!(status.changed, 2);
+        assert_eq!(status.removed, 1);
+        assert_eq!(status.unverified, 1);
+        assert_eq!(status.download_bytes, 50);
+        assert!(!status.is_up_to_date());
+    }
+
+    #[test]
+    fn cached_files_are_not_downloaded_again(){
+        let bytes = b"cached update check jar";
+        let cached = sha256_bytes(bytes);
+        store_cached(&cached, bytes).unwrap();
+        let manifest = PackManifest{
+            files: vec![file("cached.jar", Some(&cached), 100), file("fresh.jar", Some("ffff"), 7)],
+            ..Default::default()
+        };
+        let status = check_for_updates(&manifest, &InstalledPack::default(), &ModSelection::default());
+        assert_eq!(status.changed, 2);
+        assert_eq!(status.download_bytes, 7);
+    }
+
+    #[test]
+    fn pinned_packs_describe_their_revision(){
+        let status = UpdateStatus{pinned: Some("0123abcd".to_string()), ..Default::default()};
+        assert_eq!(status.describe(), "pinned to 0123abcd");
+        assert!(status.is_up_to_date());
+    }
+}
@@ -0,0 +1,265 @@
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+enum VersionToken{
+    Number(u64),
+    Qualifier(String),
+}
+
+/// Rank of a qualifier relative to a release, following maven's ComparableVersion:
+/// alpha < beta < milestone < rc < snapshot < release < sp < anything unknown.
+fn qualifier_rank(qualifier: &str) -> (u8, &str){
+    match qualifier {
+        "alpha" | "a" => (0, ""),
+        "beta" | "b" => (1, ""),
+        "milestone" | "m" => (2, ""),
+        "rc" | "cr" | "pre" => (3, ""),
+        "snapshot" => (4, ""),
+        "" | "ga" | "final" | "release" => (5, ""),
+        "sp" => (6, ""),
+        other => (7, other),
+    }
+}
+
+fn tokenize(version: &str) -> Vec<VersionToken>{
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut current_is_digit = false;
+    let push = |tokens: &mut Vec<VersionToken>, current: &mut String, is_digit: bool| {
+        if current.is_empty() {
+            return;
+        }
+        let token = match is_digit {
+            true => VersionToken::Number(current.parse().unwrap_or(u64::MAX)),
+            false => {
+                // "1.0-beta" and "1-beta" are the same version
+                while matches!(tokens.last(), Some(VersionToken::Number(0))) {
+                    tokens.pop();
+                }
+                VersionToken::Qualifier(current.to_lowercase())
+            }
+        };
+        tokens.push(token);
+        current.clear();
+    };
+    for c in version.trim().chars() {
+        if c == '.' || c == '-' || c == '+' || c == '_' {
+            push(&mut tokens, &mut current, current_is_digit);
+            continue;
+        }
+        if !current.is_empty() && c.is_ascii_digit() != current_is_digit {
+            push(&mut tokens, &mut current, current_is_digit);
+        }
+        current_is_digit = c.is_ascii_digit();
+        current.push(c);
+    }
+    push(&mut tokens, &mut current, current_is_digit);
+
+    // "1.0.0" and "1" are the same version
+    while matches!(tokens.last(), Some(VersionToken::Number(0))) {
+        tokens.pop();
+    }
+    tokens
+}
+
+fn compare_tokens(a: Option<&VersionToken>, b: Option<&VersionToken>) -> Ordering{
+    match (a, b) {
+        (Some(VersionToken::Number(a)), Some(VersionToken::Number(b))) => a.cmp(b),
+        (Some(VersionToken::Number(_)), Some(VersionToken::Qualifier(_))) => Ordering::Greater,
+        (Some(VersionToken::Qualifier(_)), Some(VersionToken::Number(_))) => Ordering::Less,
+        (Some(VersionToken::Qualifier(a)), Some(VersionToken::Qualifier(b))) => qualifier_rank(a).cmp(&qualifier_rank(b)),
+        (Some(VersionToken::Number(a)), None) => a.cmp(&0),
+        (None, Some(VersionToken::Number(b))) => 0.cmp(b),
+        (Some(VersionToken::Qualifier(a)), None) => qualifier_rank(a).cmp(&qualifier_rank("")),
+        (None, Some(VersionToken::Qualifier(b))) => qualifier_rank("").cmp(&qualifier_rank(b)),
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compares two version strings the way maven (and so forge) orders them.
+pub fn compare_versions(a: &str, b: &str) -> Ordering{
+    let a = tokenize(a);
+    let b = tokenize(b);
+    for i in 0..a.len().max(b.len()) {
+        match compare_tokens(a.get(i), b.get(i)) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    Ordering::Equal
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Restriction{
+    lower:Option<(String, bool)>,
+    upper:Option<(String, bool)>,
+}
+
+impl Restriction{
+    fn contains(&self, version: &str) -> bool{
+        if let Some((lower, inclusive)) = &self.lower {
+            match compare_versions(version, lower) {
+                Ordering::Less => return false,
+                Ordering::Equal if !inclusive => return false,
+                _ => {}
+            }
+        }
+        if let Some((upper, inclusive)) = &self.upper {
+            match compare_versions(version, upper) {
+                Ordering::Greater => return false,
+                Ordering::Equal if !inclusive => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// A maven version range like `[47,)` or `[1.20,1.20.2),[1.20.4]` as used by `versionRange`
+/// in mods.toml. A bare version or `*` is only a recommendation and accepts anything.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionRange{
+    restrictions:Vec<Restriction>,
+}
+
+impl VersionRange{
+    pub fn parse(spec: &str) -> Result<VersionRange, String>{
+        let spec = spec.trim();
+        if spec.is_empty() || spec == "*" || !(spec.starts_with('[') || spec.starts_with('(')) {
+            return Ok(VersionRange{ restrictions: vec![] });
+        }
+
+        let mut restrictions = vec![];
+        let mut rest = spec;
+        while !rest.is_empty() {
+            let lower_inclusive = match rest.chars().next() {
+                Some('[') => true,
+                Some('(') => false,
+                _ => return Err(format!("malformed version range '{spec}'")),
+            };
+            let end = match rest.find([']', ')']) {
+                Some(end) => end,
+                None => return Err(format!("unclosed version range '{spec}'")),
+            };
+            let upper_inclusive = rest[end..].starts_with(']');
+            let inner = &rest[1..end];
+
+            let restriction = match inner.split_once(',') {
+                Some((lower, upper)) => {
+                    let lower = lower.trim();
+                    let upper = upper.trim();
+                    Restriction{
+                        lower: (!lower.is_empty()).then(|| (lower.to_string(), lower_inclusive)),
+                        upper: (!upper.is_empty()).then(|| (upper.to_string(), upper_inclusive)),
+                    }
+                }
+                None => {
+                    if !lower_inclusive || !upper_inclusive {
+                        return Err(format!("single version '{inner}' must be written as [{inner}]"));
+                    }
+                    let version = inner.trim().to_string();
+                    Restriction{
+                        lower: Some((version.clone(), true)),
+                        upper: Some((version, true)),
+                    }
+                }
+            };
+            restrictions.push(restriction);
+
+            rest = rest[end + 1..].trim_start();
+            if let Some(next) = rest.strip_prefix(',') {
+                rest = next.trim_start();
+            }
+        }
+        Ok(VersionRange{ restrictions })
+    }
+
+    pub fn contains(&self, version: &str) -> bool{
+        self.restrictions.is_empty() || self.restrictions.iter().any(|restriction| restriction.contains(version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use super::{compare_versions, VersionRange};
+
+    #[test]
+    fn trailing_zeros_are_ignored() {
+        assert_eq!(compare_versions("1.0", "1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0", "1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0-beta", "1-beta"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-rc1", "1-rc1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn numbers_compare_numerically() {
+        assert_eq!(compare_versions("1.2", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("47.3.10", "47.3.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.20.1", "1.20"), Ordering::Greater);
+    }
+
+    #[test]
+    fn qualifiers_follow_maven_order() {
+        let ordered = ["1.0-alpha", "1.0-beta", "1.0-milestone", "1.0-rc", "1.0-SNAPSHOT", "1.0", "1.0-sp", "1.0-custom"];
+        for pair in ordered.windows(2) {
+            assert_eq!(compare_versions(pair[0], pair[1]), Ordering::Less, "{0} < {1}", pair[0], pair[1]);
+        }
+        assert_eq!(compare_versions("1.0-final", "1.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.1", "1.0-rc"), Ordering::Greater);
+    }
+
+    #[test]
+    fn bounds_respect_inclusiveness() {
+        let range = VersionRange::parse("[47,)").unwrap();
+        assert!(range.contains("47"));
+        assert!(range.contains("48.1"));
+        assert!(!range.contains("46.9"));
+
+        let range = VersionRange::parse("(47,48]").unwrap();
+        assert!(!range.contains("47.0"));
+        assert!(range.contains("47.1"));
+        assert!(range.contains("48"));
+        assert!(!range.contains("48.0.1"));
+
+        let range = VersionRange::parse("[1.20,1.20.2)").unwrap();
+        assert!(range.contains("1.20.1"));
+        assert!(!range.contains("1.20.2"));
+    }
+
+    #[test]
+    fn multiple_ranges_are_a_union() {
+        let range = VersionRange::parse("[1.20,1.20.2),[1.20.4]").unwrap();
+        assert!(range.contains("1.20"));
+        assert!(!range.contains("1.20.3"));
+        assert!(range.contains("1.20.4"));
+        assert!(!range.contains("1.20.5"));
+    }
+
+    #[test]
+    fn recommendations_accept_anything() {
+        for spec in ["*", "", "1.2"] {
+            let range = VersionRange::parse(spec).unwrap();
+            assert!(range.contains("0.1"));
+            assert!(range.contains("99"));
+        }
+    }
+
+    #[test]
+    fn exact_versions_and_qualifiers() {
+        let range = VersionRange::parse("[1.0]").unwrap();
+        assert!(range.contains("1.0.0"));
+        assert!(!range.contains("1.0.1"));
+
+        let range = VersionRange::parse("[1.0,)").unwrap();
+        assert!(!range.contains("1.0-beta"));
+        assert!(range.contains("1.0-sp"));
+    }
+
+    #[test]
+    fn malformed_ranges_are_rejected() {
+        assert!(VersionRange::parse("(1.0)").is_err());
+        assert!(VersionRange::parse("[1.0").is_err());
+        assert!(VersionRange::parse("[1.0,2.0]x").is_err());
+    }
+}